@@ -0,0 +1,237 @@
+//! Insight 的 Python 绑定，与桌面应用共用日志解析与查询代码
+//!
+//! ```python
+//! import insight
+//! import pandas as pd
+//!
+//! job, columns = insight.LogParser.parse_file("job.log")
+//! df = pd.DataFrame(columns)
+//!
+//! db = insight.Database()  # 读取与桌面应用相同的配置文件
+//! jobs = pd.DataFrame(db.job_list())
+//! summary, entries = db.error_log(jobs["id"][0])
+//! ```
+
+use insight_lib::api::{self, AppConfig, DatabaseConfig, PgPool};
+use pyo3::exceptions::{PyLookupError, PyOSError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde_json::Value;
+use std::path::PathBuf;
+
+fn to_py_err(err: api::Error) -> PyErr {
+    match err {
+        api::Error::Io(e) => PyOSError::new_err(e.to_string()),
+        api::Error::LogFormat(_) | api::Error::LogLine { .. } => PyValueError::new_err(err.to_string()),
+        api::Error::Sqlx(sqlx::Error::RowNotFound) => PyLookupError::new_err(err.to_string()),
+        err => PyRuntimeError::new_err(err.to_string()),
+    }
+}
+
+/// 将 JSON 值转换为对应的 Python 对象
+fn to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => b.into_pyobject(py)?.to_owned().into_any(),
+        Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => i.into_pyobject(py)?.into_any(),
+            (None, Some(f)) => f.into_pyobject(py)?.into_any(),
+            (None, None) => n.to_string().into_pyobject(py)?.into_any(),
+        },
+        Value::String(s) => s.into_pyobject(py)?.into_any(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(to_py(py, item)?)?;
+            }
+            list.into_any()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, item) in map {
+                dict.set_item(key, to_py(py, item)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+fn serialize<'py>(py: Python<'py>, value: &impl serde::Serialize) -> PyResult<Bound<'py, PyAny>> {
+    let value = serde_json::to_value(value).map_err(|e| to_py_err(e.into()))?;
+    to_py(py, &value)
+}
+
+/// 将行转换为列，返回 `{字段名: 列表}`，可直接传给 `pandas.DataFrame` 或 `pyarrow.table`
+fn columns<'py>(py: Python<'py>, rows: &[impl serde::Serialize]) -> PyResult<Bound<'py, PyDict>> {
+    let mut columns = serde_json::Map::new();
+    for row in rows {
+        let Value::Object(row) = serde_json::to_value(row).map_err(|e| to_py_err(e.into()))? else {
+            continue;
+        };
+        for (key, value) in row {
+            if let Value::Array(column) = columns
+                .entry(key)
+                .or_insert_with(|| Value::Array(Vec::with_capacity(rows.len())))
+            {
+                column.push(value);
+            }
+        }
+    }
+
+    let dict = PyDict::new(py);
+    for (key, column) in &columns {
+        dict.set_item(key, to_py(py, column)?)?;
+    }
+    Ok(dict)
+}
+
+/// 日志解析器
+#[pyclass(frozen)]
+struct LogParser;
+
+#[pymethods]
+impl LogParser {
+    /// 解析日志内容，返回 `(job, columns)`
+    ///
+    /// `job` 为日志第一行的作业信息，`columns` 为按列存储的误差日志，
+    /// 包含 `timestamp`、`load`、`iter`、`error_u`、`error_phi`。
+    #[staticmethod]
+    fn parse<'py>(
+        py: Python<'py>,
+        content: &str,
+    ) -> PyResult<(Bound<'py, PyAny>, Bound<'py, PyDict>)> {
+        let parsed = py
+            .detach(|| api::LogParser::parse(content))
+            .map_err(to_py_err)?;
+
+        let columns = PyDict::new(py);
+        columns.set_item("timestamp", &parsed.columns.timestamp)?;
+        columns.set_item("load", &parsed.columns.load)?;
+        columns.set_item("iter", &parsed.columns.iter)?;
+        columns.set_item("error_u", &parsed.columns.error_u)?;
+        columns.set_item("error_phi", &parsed.columns.error_phi)?;
+        Ok((serialize(py, &parsed.header)?, columns))
+    }
+
+    /// 读取并解析日志文件，返回值同 [`LogParser::parse`]
+    #[staticmethod]
+    fn parse_file<'py>(
+        py: Python<'py>,
+        path: PathBuf,
+    ) -> PyResult<(Bound<'py, PyAny>, Bound<'py, PyDict>)> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(py, &content)
+    }
+}
+
+/// 数据库连接，查询与桌面应用相同的数据
+#[pyclass(frozen)]
+struct Database {
+    runtime: tokio::runtime::Runtime,
+    pool: PgPool,
+}
+
+impl Database {
+    /// 在释放 GIL 的情况下执行查询
+    fn block_on<T: Send>(
+        &self,
+        py: Python<'_>,
+        future: impl std::future::Future<Output = api::Result<T>> + Send,
+    ) -> PyResult<T> {
+        py.detach(|| self.runtime.block_on(future)).map_err(to_py_err)
+    }
+}
+
+#[pymethods]
+impl Database {
+    /// 连接数据库
+    ///
+    /// 未指定 `url` 时使用配置文件中的数据库配置，配置文件默认与桌面应用相同，
+    /// 可通过 `config` 或环境变量 `INSIGHT_CONFIG` 指定。
+    #[new]
+    #[pyo3(signature = (url=None, config=None))]
+    fn new(py: Python<'_>, url: Option<String>, config: Option<PathBuf>) -> PyResult<Self> {
+        if let Some(path) = config {
+            AppConfig::set_path(path);
+        }
+        let database = match url {
+            Some(url) => DatabaseConfig::from_url(url),
+            None => AppConfig::load()
+                .and_then(|config| config.database().cloned())
+                .map_err(to_py_err)?,
+        };
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let pool = py
+            .detach(|| runtime.block_on(database.connect()))
+            .map_err(to_py_err)?;
+        Ok(Self { runtime, pool })
+    }
+
+    /// 任务列表，每个任务为一个 `dict`
+    ///
+    /// 可按标签 `tag`、备注中的文字 `note` 与是否收藏 `starred` 筛选，
+    /// `search` 匹配编号、作业号、名字、队列或参数值。`metrics` 为 `True` 时同时返回汇总指标。
+    #[pyo3(signature = (tag=None, note=None, starred=None, search=None, metrics=false))]
+    fn job_list<'py>(
+        &self,
+        py: Python<'py>,
+        tag: Option<String>,
+        note: Option<String>,
+        starred: Option<bool>,
+        search: Option<String>,
+        metrics: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let filter = api::JobFilter {
+            tag,
+            note,
+            starred,
+            search,
+            metrics,
+            ..Default::default()
+        };
+        let jobs = self.block_on(py, api::fetch_job_list(&self.pool, &filter))?;
+        serialize(py, &jobs)
+    }
+
+    fn job<'py>(&self, py: Python<'py>, job_id: i64) -> PyResult<Bound<'py, PyAny>> {
+        let job = self.block_on(py, api::fetch_job(&self.pool, job_id))?;
+        serialize(py, &job)
+    }
+
+    /// 误差日志，返回按列存储的 `(summary, entries)`
+    ///
+    /// `summary` 为每个加载步的迭代次数与耗时，`entries` 为每次迭代的误差与耗时。
+    fn error_log<'py>(
+        &self,
+        py: Python<'py>,
+        job_id: i64,
+    ) -> PyResult<(Bound<'py, PyDict>, Bound<'py, PyDict>)> {
+        let (summary, entries) = self.block_on(py, api::fetch_error_log(&self.pool, job_id))?;
+        Ok((columns(py, &summary)?, columns(py, &entries)?))
+    }
+
+    /// 任务耗时分解，参数含义同桌面应用的耗时报告
+    #[pyo3(signature = (job_id, gap_threshold=None, target_load=None))]
+    fn summary<'py>(
+        &self,
+        py: Python<'py>,
+        job_id: i64,
+        gap_threshold: Option<f64>,
+        target_load: Option<f64>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let report = self.block_on(
+            py,
+            api::TimingReport::fetch(&self.pool, job_id, gap_threshold, target_load),
+        )?;
+        serialize(py, &report)
+    }
+}
+
+#[pymodule]
+fn insight(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<LogParser>()?;
+    m.add_class::<Database>()?;
+    Ok(())
+}
@@ -0,0 +1,9 @@
+//! 任意输入都应返回错误而不是 panic
+#![no_main]
+
+use insight_lib::api::LogParser;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|logs: &str| {
+    let _ = LogParser::parse(logs);
+});
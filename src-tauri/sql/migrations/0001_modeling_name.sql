@@ -0,0 +1,37 @@
+-- 为已有数据库补充 modeling 的名称、自增主键以及 modeling_jobs 的联合主键
+
+ALTER TABLE
+    "modeling" ADD COLUMN IF NOT EXISTS "name" TEXT NOT NULL DEFAULT '';
+ALTER TABLE
+    "modeling" ALTER COLUMN "name" DROP DEFAULT;
+-- 以下各步均可重复执行，中途失败后可以重新运行整个脚本
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT FROM pg_attribute
+        WHERE attrelid = '"modeling"'::regclass AND attname = 'id' AND attidentity <> ''
+    ) THEN
+        ALTER TABLE "modeling" ALTER COLUMN "id" ADD GENERATED BY DEFAULT AS IDENTITY;
+    END IF;
+END $$;
+-- 自增序列从现有最大 id 之后开始
+SELECT setval(pg_get_serial_sequence('"modeling"', 'id'), COALESCE(MAX("id"), 0) + 1, false)
+FROM "modeling";
+
+-- 去除重复关联后再添加主键
+DELETE FROM "modeling_jobs" a
+USING "modeling_jobs" b
+WHERE a.ctid < b.ctid
+  AND a."modeling_id" = b."modeling_id"
+  AND a."job_id" = b."job_id";
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT FROM pg_constraint
+        WHERE conrelid = '"modeling_jobs"'::regclass AND contype = 'p'
+    ) THEN
+        ALTER TABLE "modeling_jobs" ADD PRIMARY KEY("modeling_id", "job_id");
+    END IF;
+END $$;
+CREATE INDEX IF NOT EXISTS "modeling_jobs_job_id_index" ON
+    "modeling_jobs"("job_id");
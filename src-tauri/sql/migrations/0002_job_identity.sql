@@ -0,0 +1,35 @@
+-- 任务 id 改为数据库分配的代理键，调度系统的原始作业号与调度系统另行保存
+-- 已有任务的作业号即原来的 id，调度系统未知
+-- 各步均可重复执行，中途失败后可以重新运行整个脚本
+
+ALTER TABLE
+    "job_info" ADD COLUMN IF NOT EXISTS "scheduler" TEXT NOT NULL DEFAULT '';
+ALTER TABLE
+    "job_info" ADD COLUMN IF NOT EXISTS "scheduler_id" TEXT;
+UPDATE "job_info" SET "scheduler_id" = "id"::TEXT WHERE "scheduler_id" IS NULL;
+ALTER TABLE
+    "job_info" ALTER COLUMN "scheduler_id" SET NOT NULL;
+ALTER TABLE
+    "job_info" ADD COLUMN IF NOT EXISTS "array_job_id" TEXT;
+ALTER TABLE
+    "job_info" ADD COLUMN IF NOT EXISTS "array_index" INTEGER;
+DO $$
+BEGIN
+    -- 0006_job_trash.sql 将其替换为同名的部分唯一索引
+    IF to_regclass('"job_info_scheduler_id_unique"') IS NULL THEN
+        ALTER TABLE "job_info" ADD CONSTRAINT "job_info_scheduler_id_unique" UNIQUE("scheduler", "scheduler_id");
+    END IF;
+END $$;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT FROM pg_attribute
+        WHERE attrelid = '"job_info"'::regclass AND attname = 'id' AND attidentity <> ''
+    ) THEN
+        ALTER TABLE "job_info" ALTER COLUMN "id" ADD GENERATED BY DEFAULT AS IDENTITY;
+    END IF;
+END $$;
+-- 自增序列从现有最大 id 之后开始
+SELECT setval(pg_get_serial_sequence('"job_info"', 'id'), COALESCE(MAX("id"), 0) + 1, false)
+FROM "job_info";
@@ -0,0 +1,4 @@
+-- 保存日志中作业信息行的全部字段，如 user、walltime、exit_status
+
+ALTER TABLE
+    "job_info" ADD COLUMN IF NOT EXISTS "metadata" jsonb NOT NULL DEFAULT '{}';
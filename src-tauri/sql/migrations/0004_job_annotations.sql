@@ -0,0 +1,19 @@
+-- 任务的标签、备注与收藏
+
+ALTER TABLE
+    "job_info" ADD COLUMN IF NOT EXISTS "starred" BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS "job_tags"(
+    "job_id" BIGINT NOT NULL REFERENCES "job_info"("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    "tag" TEXT NOT NULL,
+    PRIMARY KEY("job_id", "tag")
+);
+CREATE INDEX IF NOT EXISTS "job_tags_tag_index" ON
+    "job_tags"("tag");
+
+-- 任务的备注，内容为 Markdown
+CREATE TABLE IF NOT EXISTS "job_notes"(
+    "job_id" BIGINT NOT NULL PRIMARY KEY REFERENCES "job_info"("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    "content" TEXT NOT NULL,
+    "updated_at" TIMESTAMP(3) WITHOUT TIME ZONE NOT NULL DEFAULT now()
+);
@@ -0,0 +1,15 @@
+-- 任务的导入时间与汇总指标，已有任务的导入时间未知，汇总指标由 0007_load_steps.sql 补算
+
+ALTER TABLE
+    "job_info" ADD COLUMN IF NOT EXISTS "imported_at" TIMESTAMP(3) WITHOUT TIME ZONE;
+ALTER TABLE
+    "job_info" ALTER COLUMN "imported_at" SET DEFAULT now();
+
+CREATE TABLE IF NOT EXISTS "job_summary"(
+    "job_id" BIGINT NOT NULL PRIMARY KEY REFERENCES "job_info"("id") ON UPDATE CASCADE ON DELETE CASCADE,
+    "iterations" BIGINT NOT NULL,
+    "load_steps" INTEGER NOT NULL,
+    "failed_steps" INTEGER NOT NULL,
+    "final_load" DOUBLE PRECISION NOT NULL,
+    "wall_time" DOUBLE PRECISION NOT NULL
+);
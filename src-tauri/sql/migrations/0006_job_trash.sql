@@ -0,0 +1,14 @@
+-- 删除任务时先移入回收站，回收站中的任务不占用作业号
+
+ALTER TABLE
+    "job_info" ADD COLUMN IF NOT EXISTS "deleted_at" TIMESTAMP(3) WITHOUT TIME ZONE;
+-- 开始彻底删除后不能再恢复
+ALTER TABLE
+    "job_info" ADD COLUMN IF NOT EXISTS "purging" BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE
+    "job_info" DROP CONSTRAINT IF EXISTS "job_info_scheduler_id_unique";
+CREATE UNIQUE INDEX IF NOT EXISTS "job_info_scheduler_id_unique" ON
+    "job_info"("scheduler", "scheduler_id") WHERE "deleted_at" IS NULL;
+CREATE INDEX IF NOT EXISTS "job_info_deleted_at_index" ON
+    "job_info"("deleted_at") WHERE "deleted_at" IS NOT NULL;
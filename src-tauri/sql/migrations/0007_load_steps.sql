@@ -0,0 +1,68 @@
+-- 按载荷变化划分加载步的视图，同一载荷的重试不再与之前的加载步合并，
+-- 并由其计算任务的汇总指标，为已有任务补算或按新的划分重新计算
+
+DROP VIEW IF EXISTS job_step_summary;
+DROP VIEW IF EXISTS load_steps;
+CREATE VIEW load_steps AS
+WITH
+"marked" AS (
+  SELECT
+    "job_id",
+    "load",
+    "iter",
+    "timestamp",
+    CASE WHEN
+      "load" = lag("load") OVER "w" AND "iter" > lag("iter") OVER "w"
+    THEN 0 ELSE 1 END AS "started"
+  FROM "error_log"
+  WINDOW "w" AS (PARTITION BY "job_id" ORDER BY "timestamp", "iter")
+),
+"numbered" AS (
+  SELECT
+    *,
+    SUM("started") OVER (PARTITION BY "job_id" ORDER BY "timestamp", "iter") AS "step"
+  FROM "marked"
+),
+"steps" AS (
+  SELECT
+    "job_id",
+    "step",
+    MIN("load") AS "load",
+    MAX("iter") AS "iters",
+    MIN("timestamp") AS "started_at",
+    MAX("timestamp") AS "ended_at"
+  FROM "numbered"
+  GROUP BY "job_id", "step"
+)
+SELECT
+  "job_id",
+  "step"::INTEGER AS "step",
+  "load",
+  "iters",
+  "started_at",
+  "ended_at",
+  extract(EPOCH from coalesce(lead("started_at") OVER "w", "ended_at") - "started_at")::DOUBLE PRECISION AS "cost",
+  coalesce(lead("load") OVER "w" > "load", true) AS "converged"
+FROM "steps"
+WINDOW "w" AS (PARTITION BY "job_id" ORDER BY "step");
+
+CREATE VIEW job_step_summary AS
+SELECT
+  "job_id",
+  SUM("iters")::BIGINT AS "iterations",
+  (COUNT(*) FILTER (WHERE "converged"))::INTEGER AS "load_steps",
+  (COUNT(*) FILTER (WHERE NOT "converged"))::INTEGER AS "failed_steps",
+  (array_agg("load" ORDER BY "step" DESC))[1] AS "final_load",
+  extract(EPOCH from MAX("ended_at") - MIN("started_at"))::DOUBLE PRECISION AS "wall_time"
+FROM "load_steps"
+GROUP BY "job_id";
+
+INSERT INTO job_summary (job_id, iterations, load_steps, failed_steps, final_load, wall_time)
+SELECT job_id, iterations, load_steps, failed_steps, final_load, wall_time
+FROM job_step_summary
+ON CONFLICT (job_id) DO UPDATE SET
+    iterations = EXCLUDED.iterations,
+    load_steps = EXCLUDED.load_steps,
+    failed_steps = EXCLUDED.failed_steps,
+    final_load = EXCLUDED.final_load,
+    wall_time = EXCLUDED.wall_time;
@@ -1,15 +1,36 @@
 CREATE TABLE "job_info"(
-    "id" BIGINT NOT NULL,
+    "id" BIGINT GENERATED BY DEFAULT AS IDENTITY NOT NULL,
     "name" TEXT NOT NULL,
     "queue" TEXT NOT NULL,
     "num_cpu" INTEGER NOT NULL,
     "nodes" TEXT[] NOT NULL,
-    "paramters" jsonb NOT NULL
+    "parameters" jsonb,
+    -- 调度系统，无法判断时为空
+    "scheduler" TEXT NOT NULL DEFAULT '',
+    -- 调度系统的原始作业号，如 12345.pbs01、123[4]、123_4
+    "scheduler_id" TEXT NOT NULL,
+    -- 数组作业的父作业号与序号
+    "array_job_id" TEXT,
+    "array_index" INTEGER,
+    -- 日志中作业信息行的全部字段
+    "metadata" jsonb NOT NULL DEFAULT '{}',
+    "starred" BOOLEAN NOT NULL DEFAULT FALSE,
+    -- 导入时间，早于该列加入的任务为空
+    "imported_at" TIMESTAMP(3) WITHOUT TIME ZONE DEFAULT now(),
+    -- 移入回收站的时间，为空表示未删除
+    "deleted_at" TIMESTAMP(3) WITHOUT TIME ZONE,
+    -- 正在彻底删除，误差日志可能已删除一部分，不能再恢复
+    "purging" BOOLEAN NOT NULL DEFAULT FALSE
 );
 ALTER TABLE
     "job_info" ADD PRIMARY KEY("id");
 CREATE INDEX "job_info_id_index" ON
     "job_info"("id");
+-- 回收站中的任务不占用作业号，可以重新导入
+CREATE UNIQUE INDEX "job_info_scheduler_id_unique" ON
+    "job_info"("scheduler", "scheduler_id") WHERE "deleted_at" IS NULL;
+CREATE INDEX "job_info_deleted_at_index" ON
+    "job_info"("deleted_at") WHERE "deleted_at" IS NOT NULL;
 
 -----------------------------------------------------------
 
@@ -61,10 +82,106 @@ ORDER BY
   "duration"."job_id" ASC, 
   "duration"."load" ASC;
 
+-- 按时间顺序划分的加载步，载荷变化或迭代次数重新计数时开始新的加载步，
+-- 因此同一载荷的重试是两个加载步。下一个加载步的载荷不大于当前载荷时当前加载步不收敛，
+-- 最后一个加载步视为收敛。cost 为距下一个加载步开始的秒数，最后一个加载步为其自身持续时间
+CREATE VIEW load_steps AS
+WITH
+"marked" AS (
+  SELECT
+    "job_id",
+    "load",
+    "iter",
+    "timestamp",
+    CASE WHEN
+      "load" = lag("load") OVER "w" AND "iter" > lag("iter") OVER "w"
+    THEN 0 ELSE 1 END AS "started"
+  FROM "error_log"
+  WINDOW "w" AS (PARTITION BY "job_id" ORDER BY "timestamp", "iter")
+),
+"numbered" AS (
+  SELECT
+    *,
+    SUM("started") OVER (PARTITION BY "job_id" ORDER BY "timestamp", "iter") AS "step"
+  FROM "marked"
+),
+"steps" AS (
+  SELECT
+    "job_id",
+    "step",
+    MIN("load") AS "load",
+    MAX("iter") AS "iters",
+    MIN("timestamp") AS "started_at",
+    MAX("timestamp") AS "ended_at"
+  FROM "numbered"
+  GROUP BY "job_id", "step"
+)
+SELECT
+  "job_id",
+  "step"::INTEGER AS "step",
+  "load",
+  "iters",
+  "started_at",
+  "ended_at",
+  extract(EPOCH from coalesce(lead("started_at") OVER "w", "ended_at") - "started_at")::DOUBLE PRECISION AS "cost",
+  coalesce(lead("load") OVER "w" > "load", true) AS "converged"
+FROM "steps"
+WINDOW "w" AS (PARTITION BY "job_id" ORDER BY "step");
+
+-- 由 load_steps 计算的任务汇总指标，写入 job_summary 时使用，按 job_id 筛选时只扫描这些任务
+CREATE VIEW job_step_summary AS
+SELECT
+  "job_id",
+  SUM("iters")::BIGINT AS "iterations",
+  (COUNT(*) FILTER (WHERE "converged"))::INTEGER AS "load_steps",
+  (COUNT(*) FILTER (WHERE NOT "converged"))::INTEGER AS "failed_steps",
+  (array_agg("load" ORDER BY "step" DESC))[1] AS "final_load",
+  extract(EPOCH from MAX("ended_at") - MIN("started_at"))::DOUBLE PRECISION AS "wall_time"
+FROM "load_steps"
+GROUP BY "job_id";
+
+-----------------------------------------------------------
+
+-- 任务的汇总指标，导入时由 job_step_summary 计算，查询任务列表时无需扫描 error_log
+CREATE TABLE "job_summary"(
+    "job_id" BIGINT NOT NULL,
+    -- 所有加载步的迭代次数之和
+    "iterations" BIGINT NOT NULL,
+    -- 收敛与不收敛的加载步数，见 load_steps
+    "load_steps" INTEGER NOT NULL,
+    "failed_steps" INTEGER NOT NULL,
+    "final_load" DOUBLE PRECISION NOT NULL,
+    -- 第一条与最后一条日志的间隔（秒）
+    "wall_time" DOUBLE PRECISION NOT NULL
+);
+ALTER TABLE
+    "job_summary" ADD PRIMARY KEY("job_id");
+
+-----------------------------------------------------------
+
+CREATE TABLE "job_tags"(
+    "job_id" BIGINT NOT NULL,
+    "tag" TEXT NOT NULL
+);
+ALTER TABLE
+    "job_tags" ADD PRIMARY KEY("job_id", "tag");
+CREATE INDEX "job_tags_tag_index" ON
+    "job_tags"("tag");
+
+-- 任务的备注，内容为 Markdown
+CREATE TABLE "job_notes"(
+    "job_id" BIGINT NOT NULL,
+    "content" TEXT NOT NULL,
+    "updated_at" TIMESTAMP(3) WITHOUT TIME ZONE NOT NULL DEFAULT now()
+);
+ALTER TABLE
+    "job_notes" ADD PRIMARY KEY("job_id");
+
 -----------------------------------------------------------
 
 CREATE TABLE "modeling"(
-    "id" INTEGER NOT NULL,
+    "id" INTEGER GENERATED BY DEFAULT AS IDENTITY NOT NULL,
+    "name" TEXT NOT NULL,
     "desc" TEXT
 );
 ALTER TABLE
@@ -76,6 +193,10 @@ CREATE TABLE "modeling_jobs"(
     "modeling_id" INTEGER NOT NULL,
     "job_id" BIGINT NOT NULL
 );
+ALTER TABLE
+    "modeling_jobs" ADD PRIMARY KEY("modeling_id", "job_id");
+CREATE INDEX "modeling_jobs_job_id_index" ON
+    "modeling_jobs"("job_id");
 
 -----------------------------------------------------------
 -- Relations
@@ -87,6 +208,18 @@ ALTER TABLE
     "modeling_jobs" ADD CONSTRAINT "modeling_jobs_job_id_foreign" FOREIGN KEY("job_id") REFERENCES "job_info"("id")
     ON UPDATE CASCADE
     ON DELETE CASCADE;
+ALTER TABLE
+    "job_summary" ADD CONSTRAINT "job_summary_job_id_foreign" FOREIGN KEY("job_id") REFERENCES "job_info"("id")
+    ON UPDATE CASCADE
+    ON DELETE CASCADE;
+ALTER TABLE
+    "job_tags" ADD CONSTRAINT "job_tags_job_id_foreign" FOREIGN KEY("job_id") REFERENCES "job_info"("id")
+    ON UPDATE CASCADE
+    ON DELETE CASCADE;
+ALTER TABLE
+    "job_notes" ADD CONSTRAINT "job_notes_job_id_foreign" FOREIGN KEY("job_id") REFERENCES "job_info"("id")
+    ON UPDATE CASCADE
+    ON DELETE CASCADE;
 ALTER TABLE
     "error_log" ADD CONSTRAINT "error_log_job_id_foreign" FOREIGN KEY("job_id") REFERENCES "job_info"("id")
     ON UPDATE CASCADE
@@ -0,0 +1,428 @@
+//! 无界面的命令行工具，复用桌面应用的日志解析、配置与查询逻辑，
+//! 可在集群作业结束时的钩子脚本中调用。
+
+use crate::commands::{
+    delete_job, fetch_error_log_summary, fetch_job, fetch_job_page, fetch_purgeable, import_log_file,
+    purge_job, undelete_job, Archive, ExportFormat, JobExport, JobFilter, JobPageRequest, JobSortKey,
+    PurgeRequest, SortOrder, TimingReport,
+};
+use crate::config::AppConfig;
+use crate::error::Locale;
+use crate::Result;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Parser)]
+#[command(name = "insight-cli", version, about = "Import, query and export solver logs")]
+struct Cli {
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Output::Table, global = true)]
+    output: Output,
+
+    /// Config file, defaults to $INSIGHT_CONFIG or config.json in the app config directory
+    #[arg(short, long, global = true)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Output {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Import log files or job archives
+    Import {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// List jobs, optionally filtered, searched, sorted and paginated
+    List {
+        /// Only jobs with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only jobs whose note contains this text
+        #[arg(long)]
+        note: Option<String>,
+        /// Only starred jobs
+        #[arg(long)]
+        starred: bool,
+        /// Match id, scheduler id, name, queue or parameter values
+        #[arg(short, long)]
+        search: Option<String>,
+        #[arg(long, value_enum, default_value_t = JobSortKey::Id)]
+        sort: JobSortKey,
+        #[arg(long, value_enum, default_value_t = SortOrder::Asc)]
+        order: SortOrder,
+        /// Jobs per page, all jobs if omitted
+        #[arg(short = 'n', long)]
+        limit: Option<u32>,
+        /// Cursor of the next page printed by the previous call
+        #[arg(long)]
+        cursor: Option<String>,
+        /// Skip this many jobs, e.g. to jump to a page without a cursor
+        #[arg(long)]
+        offset: Option<u32>,
+        /// List jobs in the trash instead
+        #[arg(long)]
+        deleted: bool,
+    },
+    /// Show job information
+    Show { job: i64 },
+    /// Show timing breakdown and load step summary of a job
+    Summary { job: i64 },
+    /// Export jobs to CSV, Parquet, XLSX or a job archive
+    Export {
+        #[arg(required = true)]
+        jobs: Vec<i64>,
+        /// Output file
+        #[arg(short, long)]
+        file: PathBuf,
+        /// Inferred from the file extension if omitted
+        #[arg(long, value_enum, conflicts_with = "archive")]
+        format: Option<ExportFormat>,
+        /// Write a portable job archive instead
+        #[arg(long)]
+        archive: bool,
+    },
+    /// Move jobs to the trash
+    Rm {
+        #[arg(required = true)]
+        jobs: Vec<i64>,
+    },
+    /// Restore jobs from the trash
+    Restore {
+        #[arg(required = true)]
+        jobs: Vec<i64>,
+    },
+    /// Permanently delete jobs in the trash, all of them if no job or age is given
+    Purge {
+        jobs: Vec<i64>,
+        /// Only jobs trashed more than this many days ago
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u32>,
+    },
+}
+
+/// 解析命令行参数并执行，返回进程退出码
+pub fn run() -> i32 {
+    let cli = Cli::parse();
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build tokio runtime");
+
+    match runtime.block_on(execute(cli)) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(err) => {
+            eprintln!("error: {}", err.localized(Locale::current()));
+            1
+        }
+    }
+}
+
+/// 执行子命令，部分失败时返回 `Ok(false)`
+async fn execute(cli: Cli) -> Result<bool> {
+    if let Some(path) = &cli.config {
+        AppConfig::set_path(path);
+    }
+    let config = AppConfig::load()?;
+    let pool = config.database()?.connect().await?;
+    let output = cli.output;
+
+    match cli.command {
+        Command::Import { files } => {
+            let mut results = Vec::with_capacity(files.len());
+            for file in files {
+                let imported = import(&pool, &file).await;
+                results.push(match imported {
+                    Ok(job_ids) => serde_json::json!({ "file": file, "job_ids": job_ids }),
+                    Err(err) => {
+                        let error = err.localized(Locale::current());
+                        serde_json::json!({ "file": file, "error": error })
+                    }
+                });
+            }
+            let succeeded = results.iter().all(|result| result.get("error").is_none());
+            render(output, &results, &["file", "job_ids", "error"])?;
+            Ok(succeeded)
+        }
+        Command::List {
+            tag,
+            note,
+            starred,
+            search,
+            sort,
+            order,
+            limit,
+            cursor,
+            offset,
+            deleted,
+        } => {
+            let filter = JobFilter {
+                tag,
+                note,
+                starred: starred.then_some(true),
+                search,
+                deleted,
+                metrics: true,
+            };
+            let request = JobPageRequest {
+                sort,
+                order,
+                cursor,
+                limit,
+                offset,
+            };
+            let page = fetch_job_page(&pool, &filter, &request).await?;
+            let columns = [
+                "id",
+                "scheduler_id",
+                "name",
+                "queue",
+                "num_cpu",
+                "iterations",
+                "failed_steps",
+                "final_load",
+                "wall_time",
+                "starred",
+                "tags",
+            ];
+            render(output, &page.jobs, &columns)?;
+            // 输出到 stderr，不影响 JSON 输出的解析
+            if let Some(next) = &page.next_cursor {
+                eprintln!("{} of {} jobs, next page: --cursor '{next}'", page.jobs.len(), page.total);
+            }
+            Ok(true)
+        }
+        Command::Show { job } => {
+            let job = fetch_job(&pool, job).await?;
+            let columns = [
+                "id",
+                "scheduler",
+                "scheduler_id",
+                "array_job_id",
+                "array_index",
+                "name",
+                "queue",
+                "num_cpu",
+                "nodes",
+                "parameters",
+                "metadata",
+                "starred",
+                "tags",
+                "imported_at",
+                "iterations",
+                "load_steps",
+                "failed_steps",
+                "final_load",
+                "wall_time",
+                "deleted_at",
+            ];
+            render(output, &job, &columns)?;
+            Ok(true)
+        }
+        Command::Summary { job } => {
+            let (timing, steps) = tokio::try_join!(
+                TimingReport::fetch(&pool, job, None, None),
+                fetch_error_log_summary(&pool, job),
+            )?;
+            match output {
+                Output::Json => {
+                    let summary = serde_json::json!({ "timing": timing, "steps": steps });
+                    render(output, &summary, &[])?;
+                }
+                Output::Table => {
+                    let columns = [
+                        "started_at",
+                        "ended_at",
+                        "total_time",
+                        "converged_time",
+                        "failed_time",
+                        "idle_time",
+                        "remaining_time",
+                    ];
+                    render(output, &timing, &columns)?;
+                    println!();
+                    render(output, &steps, &["load", "iters", "cost"])?;
+                }
+            }
+            Ok(true)
+        }
+        Command::Export {
+            jobs,
+            file,
+            format,
+            archive,
+        } => {
+            if archive {
+                let archive = Archive::fetch(&pool, &jobs).await?;
+                archive.write_to(BufWriter::new(File::create(&file)?))?;
+            } else {
+                let format = ExportFormat::resolve(format, &file)?;
+                let mut exports = Vec::with_capacity(jobs.len());
+                for job_id in jobs {
+                    exports.push(JobExport::fetch(&pool, job_id).await?);
+                }
+                format.write(&exports, &file)?;
+            }
+            Ok(true)
+        }
+        Command::Rm { jobs } => {
+            for &job_id in &jobs {
+                delete_job(&pool, job_id).await?;
+            }
+            render(output, &jobs, &[])?;
+            Ok(true)
+        }
+        Command::Restore { jobs } => {
+            for &job_id in &jobs {
+                undelete_job(&pool, job_id).await?;
+            }
+            render(output, &jobs, &[])?;
+            Ok(true)
+        }
+        Command::Purge { jobs, older_than } => {
+            let request = PurgeRequest {
+                job_ids: (!jobs.is_empty()).then_some(jobs),
+                older_than_days: older_than,
+            };
+            let mut purged = Vec::new();
+            for job_id in fetch_purgeable(&pool, &request).await? {
+                if purge_job(&pool, job_id).await? {
+                    purged.push(job_id);
+                }
+            }
+            render(output, &purged, &[])?;
+            Ok(true)
+        }
+    }
+}
+
+/// 导入日志文件或归档文件，返回导入的任务 id
+async fn import(pool: &PgPool, file: &Path) -> Result<Vec<i64>> {
+    if Archive::is_archive(file)? {
+        let archive = Archive::read_from(BufReader::new(File::open(file)?))?;
+        archive.store(pool).await
+    } else {
+        Ok(vec![import_log_file(pool, file).await?])
+    }
+}
+
+/// 按输出格式打印
+///
+/// 表格模式下，对象按 `columns` 的顺序打印为键值两列，对象数组按 `columns` 打印为多列，
+/// 其他值直接打印。
+fn render(output: Output, value: &impl serde::Serialize, columns: &[&str]) -> Result<()> {
+    let value = serde_json::to_value(value)?;
+    if let Output::Json = output {
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+
+    match &value {
+        Value::Object(map) => {
+            let rows = columns
+                .iter()
+                .map(|&key| vec![key.to_owned(), cell(map.get(key))])
+                .collect::<Vec<_>>();
+            print_table(&[], &rows);
+        }
+        Value::Array(items) if items.iter().all(Value::is_object) && !columns.is_empty() => {
+            let rows = items
+                .iter()
+                .map(|item| columns.iter().map(|&key| cell(item.get(key))).collect())
+                .collect::<Vec<_>>();
+            print_table(columns, &rows);
+        }
+        Value::Array(items) => items.iter().for_each(|item| println!("{}", cell(Some(item)))),
+        other => println!("{}", cell(Some(other))),
+    }
+    Ok(())
+}
+
+/// 表格单元格的文本，空值显示为 `-`，数组以逗号连接
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::from("-"),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| cell(Some(item)))
+            .collect::<Vec<_>>()
+            .join(","),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let columns = rows.iter().map(Vec::len).chain([headers.len()]).max().unwrap_or(0);
+    let mut widths = vec![0; columns];
+    let lines = std::iter::once(headers.iter().map(|h| h.to_string()).collect::<Vec<_>>())
+        .filter(|header| !header.is_empty())
+        .chain(rows.iter().cloned())
+        .collect::<Vec<_>>();
+    for line in &lines {
+        for (width, text) in widths.iter_mut().zip(line) {
+            *width = (*width).max(text.chars().count());
+        }
+    }
+    for line in &lines {
+        let text = line
+            .iter()
+            .zip(&widths)
+            .map(|(text, &width)| format!("{text:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", text.trim_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cell() {
+        assert_eq!(cell(None), "-");
+        assert_eq!(cell(Some(&Value::Null)), "-");
+        assert_eq!(cell(Some(&json!("node1"))), "node1");
+        assert_eq!(cell(Some(&json!(["node1", "node2"]))), "node1,node2");
+        assert_eq!(cell(Some(&json!(4))), "4");
+        assert_eq!(cell(Some(&json!({ "mesh": 1 }))), r#"{"mesh":1}"#);
+    }
+
+    #[test]
+    fn test_parse_export() {
+        let cli = Cli::try_parse_from(["insight-cli", "export", "1", "2", "-f", "out.parquet"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Export { ref jobs, format: None, archive: false, .. } if jobs == &[1, 2]
+        ));
+        assert!(Cli::try_parse_from(["insight-cli", "export", "1", "-f", "a", "--format", "csv", "--archive"]).is_err());
+        assert!(Cli::try_parse_from(["insight-cli", "import"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let cli = Cli::try_parse_from(["insight-cli", "list", "--sort", "num-cpu", "--order", "desc", "-n", "20"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::List { sort: JobSortKey::NumCpu, order: SortOrder::Desc, limit: Some(20), .. }
+        ));
+        assert!(Cli::try_parse_from(["insight-cli", "list", "--sort", "nodes"]).is_err());
+
+        let cli = Cli::try_parse_from(["insight-cli", "purge", "--older-than", "30"]).unwrap();
+        assert!(matches!(cli.command, Command::Purge { ref jobs, older_than: Some(30) } if jobs.is_empty()));
+    }
+}
@@ -1,19 +1,97 @@
+// 仅通过 `api` 使用时（如 Python 绑定），命令行与桌面应用专用的辅助函数不会被用到
+#![cfg_attr(
+    not(any(feature = "desktop", feature = "cli")),
+    allow(dead_code, unused_imports)
+)]
+
+#[cfg(feature = "desktop")]
+mod annotation;
+mod archive;
+#[cfg_attr(not(feature = "desktop"), allow(dead_code))]
+mod connection;
 mod error_log;
 mod job;
+#[cfg(feature = "desktop")]
 mod config;
+mod export;
+#[cfg(feature = "desktop")]
+mod ipc;
+#[cfg(feature = "desktop")]
+mod modeling;
+#[cfg(feature = "desktop")]
+mod scaling;
+#[cfg_attr(not(feature = "desktop"), allow(dead_code))]
+mod statistics;
+mod timing;
 
+#[cfg(feature = "desktop")]
+pub use annotation::*;
+#[cfg_attr(not(feature = "desktop"), allow(unused_imports))]
+pub use archive::*;
+#[cfg_attr(not(feature = "desktop"), allow(unused_imports))]
+pub use connection::*;
 pub use error_log::*;
 pub use job::*;
+#[cfg(feature = "desktop")]
 pub use config::*;
+pub use export::*;
+#[cfg(feature = "desktop")]
+pub(crate) use ipc::*;
+#[cfg(feature = "desktop")]
+pub use modeling::*;
+#[cfg(feature = "desktop")]
+pub use scaling::*;
+pub use statistics::*;
+pub use timing::*;
 
 use super::Result;
-use sqlx::postgres::{PgArguments, PgRow};
-use sqlx::PgPool;
-use std::ops::Deref;
-use tauri::State;
-use tokio::sync::RwLock;
+#[cfg(feature = "desktop")]
+use {
+    crate::error::Error,
+    futures_util::TryStreamExt,
+    sqlx::postgres::{PgArguments, PgRow},
+    sqlx::Row,
+    sqlx::PgPool,
+    std::ops::Deref,
+    tauri::{AppHandle, Emitter, State},
+    tokio::sync::RwLock,
+};
+
+/// 依次绑定参数，构建 [`PgArguments`]
+///
+/// 编码失败时以 [`sqlx::Error::Encode`] 提前返回
+macro_rules! pg_arguments {
+    ($($value:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut arguments = sqlx::postgres::PgArguments::default();
+        $(sqlx::Arguments::add(&mut arguments, $value).map_err(sqlx::Error::Encode)?;)*
+        arguments
+    }};
+}
+pub(crate) use pg_arguments;
+
+/// 后台任务的错误，命令已经返回，通过 `background-error` 事件通知前端
+#[cfg(feature = "desktop")]
+#[derive(serde::Serialize)]
+pub(crate) struct BackgroundError<'a> {
+    /// 出错的后台任务，如 `purge`、`server`
+    pub(crate) task: &'static str,
+    /// 相关的任务 id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) job_id: Option<i64>,
+    pub(crate) error: &'a Error,
+}
+
+#[cfg(feature = "desktop")]
+impl BackgroundError<'_> {
+    /// 事件只在应用退出时发送失败，此时无需处理
+    pub(crate) fn emit(&self, app: &AppHandle) {
+        let _ = app.emit("background-error", self);
+    }
+}
 
 /// 通用函数，适用于不同的 SQL 查询和参数
+#[cfg(feature = "desktop")]
 async fn query_as_and_send<'q, T>(
     stmt: &'q str,
     arguments: PgArguments,
@@ -30,3 +108,69 @@ where
     channel.send(rmp_serde::to_vec(&data)?)?;
     Ok(())
 }
+
+/// 分块发送时每条 MessagePack 消息的最大行数
+#[cfg(feature = "desktop")]
+pub(crate) const MSGPACK_CHUNK_ROWS: usize = 4096;
+
+/// [`query_as_and_send`] 的流式版本，边查询边按 [`MSGPACK_CHUNK_ROWS`] 行分块发送
+///
+/// 每条消息为若干行组成的 MessagePack 数组，最后发送一条空消息表示结束。
+#[cfg(feature = "desktop")]
+async fn query_as_and_stream<'q, T>(
+    stmt: &'q str,
+    arguments: PgArguments,
+    pool: State<'_, RwLock<PgPool>>,
+    channel: tauri::ipc::Channel<Vec<u8>>,
+) -> Result<()>
+where
+    T: Send + Unpin + serde::Serialize + for<'r> sqlx::FromRow<'r, PgRow>,
+{
+    let pool = pool.read().await;
+    let mut rows = sqlx::query_as_with::<_, T, PgArguments>(stmt, arguments).fetch(pool.deref());
+    let mut chunk = Vec::with_capacity(MSGPACK_CHUNK_ROWS);
+    while let Some(row) = rows.try_next().await? {
+        chunk.push(row);
+        if chunk.len() == MSGPACK_CHUNK_ROWS {
+            channel.send(rmp_serde::to_vec(&chunk)?)?;
+            chunk.clear();
+        }
+    }
+    if !chunk.is_empty() {
+        channel.send(rmp_serde::to_vec(&chunk)?)?;
+    }
+    channel.send(Vec::new())?;
+    Ok(())
+}
+
+/// [`query_as_and_stream`] 的列式版本，边查询边按 [`ARROW_BATCH_ROWS`] 行分块发送
+///
+/// 列类型由查询结果决定，每条消息为表 `table` 的一个 record batch，见 [`ipc_message`]，
+/// 最后发送一条空消息表示结束。
+#[cfg(feature = "desktop")]
+async fn query_and_send_arrow(
+    stmt: &str,
+    table: &str,
+    arguments: PgArguments,
+    pool: State<'_, RwLock<PgPool>>,
+    channel: tauri::ipc::Channel<Vec<u8>>,
+) -> Result<()> {
+    let pool = pool.read().await;
+    let mut rows = sqlx::query_with(stmt, arguments).fetch(pool.deref());
+    let mut builder = None;
+    while let Some(row) = rows.try_next().await? {
+        let batch = match &mut builder {
+            Some(builder) => builder,
+            None => builder.insert(RowBatchBuilder::new(row.columns())?),
+        };
+        batch.append(&row)?;
+        if batch.len() >= ARROW_BATCH_ROWS {
+            channel.send(ipc_message(&batch.finish()?, table)?)?;
+        }
+    }
+    if let Some(batch) = builder.as_mut().filter(|batch| batch.len() > 0) {
+        channel.send(ipc_message(&batch.finish()?, table)?)?;
+    }
+    channel.send(Vec::new())?;
+    Ok(())
+}
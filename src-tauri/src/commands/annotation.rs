@@ -0,0 +1,136 @@
+use super::Result;
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use std::collections::BTreeSet;
+use std::ops::Deref;
+use tauri::State;
+use tokio::sync::RwLock;
+
+/// 标签及带有该标签的任务数
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+pub struct TagCount {
+    tag: String,
+    jobs: i64,
+}
+
+/// 任务的备注，内容为 Markdown
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+pub struct JobNote {
+    content: String,
+    updated_at: NaiveDateTime,
+}
+
+/// 去除首尾空白，忽略空标签与重复标签，按字母顺序排列
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    tags.iter()
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_owned)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// 所有标签，用于筛选与输入提示，不统计回收站中的任务
+#[tauri::command]
+pub async fn get_tags(pool: State<'_, RwLock<PgPool>>) -> Result<Vec<TagCount>> {
+    let stmt = r#"
+        SELECT t.tag, COUNT(*) AS jobs
+        FROM job_tags t
+        JOIN job_info j ON j.id = t.job_id
+        WHERE j.deleted_at IS NULL
+        GROUP BY t.tag
+        ORDER BY t.tag;"#;
+    let pool = pool.read().await;
+    Ok(sqlx::query_as(stmt).fetch_all(pool.deref()).await?)
+}
+
+/// 替换任务的全部标签，返回整理后的标签
+#[tauri::command]
+pub async fn set_job_tags(
+    job_id: i64,
+    tags: Vec<String>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<Vec<String>> {
+    let tags = normalize_tags(tags);
+    let pool = pool.read().await;
+    let mut trans = pool.begin().await?;
+    sqlx::query("DELETE FROM job_tags WHERE job_id = $1;")
+        .bind(job_id)
+        .execute(&mut *trans)
+        .await?;
+    sqlx::query("INSERT INTO job_tags (job_id, tag) SELECT $1, unnest($2::TEXT[]);")
+        .bind(job_id)
+        .bind(&tags)
+        .execute(&mut *trans)
+        .await?;
+    trans.commit().await?;
+    Ok(tags)
+}
+
+/// 收藏或取消收藏任务
+#[tauri::command]
+pub async fn set_job_starred(
+    job_id: i64,
+    starred: bool,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let pool = pool.read().await;
+    let result = sqlx::query("UPDATE job_info SET starred = $2 WHERE id = $1;")
+        .bind(job_id)
+        .bind(starred)
+        .execute(pool.deref())
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+    Ok(())
+}
+
+/// 任务的备注，没有备注时返回 `None`
+#[tauri::command]
+pub async fn get_job_note(
+    job_id: i64,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<Option<JobNote>> {
+    let stmt = r#"
+        SELECT content, updated_at
+        FROM job_notes
+        WHERE job_id = $1;"#;
+    let pool = pool.read().await;
+    Ok(sqlx::query_as(stmt)
+        .bind(job_id)
+        .fetch_optional(pool.deref())
+        .await?)
+}
+
+/// 保存任务的备注，内容为空时删除备注
+#[tauri::command]
+pub async fn set_job_note(
+    job_id: i64,
+    content: String,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let pool = pool.read().await;
+    let query = if content.trim().is_empty() {
+        sqlx::query("DELETE FROM job_notes WHERE job_id = $1;").bind(job_id)
+    } else {
+        let stmt = r#"
+            INSERT INTO job_notes (job_id, content) VALUES ($1, $2)
+            ON CONFLICT (job_id) DO UPDATE SET content = EXCLUDED.content, updated_at = now();"#;
+        sqlx::query(stmt).bind(job_id).bind(content)
+    };
+    query.execute(pool.deref()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = ["mesh-b", " baseline ", "", "mesh-b", "  "].map(String::from);
+        assert_eq!(normalize_tags(tags.to_vec()), ["baseline", "mesh-b"]);
+    }
+}
@@ -0,0 +1,282 @@
+use super::{refresh_job_summary, Result};
+use crate::error::Error;
+use chrono::NaiveDateTime;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sqlx::PgPool;
+use std::fs::File;
+use std::io::{Read, Write};
+#[cfg(feature = "cli")]
+use std::path::Path;
+#[cfg(feature = "desktop")]
+use {
+    std::io::{BufReader, BufWriter},
+    std::ops::Deref,
+    std::path::PathBuf,
+    tauri::State,
+    tokio::sync::RwLock,
+};
+
+/// 作业归档，可在没有数据库的情况下分享计算结果
+///
+/// # 文件格式
+///
+/// ```text
+/// | MAGIC (8 bytes) | version (u16, little endian) | gzip(MessagePack(Archive)) |
+/// ```
+///
+/// 归档内容以带字段名的 MessagePack 编码，新增字段时旧文件仍可读取；
+/// 不兼容的结构变更需提升 [`Archive::VERSION`] 并在 [`Archive::read_from`] 中升级旧版本。
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Archive {
+    jobs: Vec<ArchivedJob>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct ArchivedJob {
+    info: ArchivedJobInfo,
+    error_log: Vec<ArchivedLogRow>,
+}
+
+/// `job_info` 表中的一行
+///
+/// `id` 仅在导出的数据库中有效，导入时重新分配。早期的归档没有调度系统的作业号，以 `id` 代替。
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+struct ArchivedJobInfo {
+    id: i64,
+    name: String,
+    queue: String,
+    num_cpu: i32,
+    nodes: Vec<String>,
+    parameters: Option<serde_json::Value>,
+    #[serde(default)]
+    scheduler: String,
+    #[serde(default)]
+    scheduler_id: Option<String>,
+    #[serde(default)]
+    array_job_id: Option<String>,
+    #[serde(default)]
+    array_index: Option<i32>,
+    #[serde(default)]
+    metadata: Option<serde_json::Value>,
+}
+
+/// `error_log` 表中的一行
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+struct ArchivedLogRow {
+    timestamp: NaiveDateTime,
+    load: f64,
+    iter: i32,
+    error_u: f64,
+    error_phi: f64,
+}
+
+impl Archive {
+    const MAGIC: &'static [u8; 8] = b"INSIGHT\0";
+    const VERSION: u16 = 1;
+
+    pub(crate) fn write_to(&self, writer: impl Write) -> Result<()> {
+        let mut writer = writer;
+        writer.write_all(Self::MAGIC)?;
+        writer.write_all(&Self::VERSION.to_le_bytes())?;
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        rmp_serde::encode::write_named(&mut encoder, self)?;
+        encoder.finish()?.flush()?;
+        Ok(())
+    }
+
+    pub(crate) fn read_from(reader: impl Read) -> Result<Self> {
+        let mut reader = reader;
+        let mut magic = [0u8; 8];
+        let mut version = [0u8; 2];
+        reader
+            .read_exact(&mut magic)
+            .and_then(|_| reader.read_exact(&mut version))
+            .map_err(|_| Error::ArchiveFormat(String::from("file is too short")))?;
+        if &magic != Self::MAGIC {
+            return Err(Error::ArchiveFormat(String::from("not an insight archive")));
+        }
+
+        match u16::from_le_bytes(version) {
+            Self::VERSION => Ok(rmp_serde::from_read(GzDecoder::new(reader))?),
+            version => Err(Error::ArchiveFormat(format!(
+                "unsupported archive version {version}, expected {}",
+                Self::VERSION
+            ))),
+        }
+    }
+
+    /// 判断文件是否以归档文件头开始
+    #[cfg(feature = "cli")]
+    pub(crate) fn is_archive(path: &Path) -> Result<bool> {
+        let mut magic = [0u8; 8];
+        let mut file = File::open(path)?;
+        Ok(file.read_exact(&mut magic).is_ok() && &magic == Self::MAGIC)
+    }
+
+    pub(crate) async fn fetch(pool: &PgPool, job_ids: &[i64]) -> Result<Self> {
+        let stmt_info = r#"
+            SELECT
+                id, name, queue, num_cpu, nodes, parameters,
+                scheduler, scheduler_id, array_job_id, array_index, metadata
+            FROM job_info
+            WHERE id = $1;"#;
+        let stmt_log = r#"
+            SELECT timestamp, load, iter, error_u, error_phi
+            FROM error_log
+            WHERE job_id = $1
+            ORDER BY timestamp;"#;
+
+        let mut jobs = Vec::with_capacity(job_ids.len());
+        for &job_id in job_ids {
+            let (info, error_log) = tokio::try_join!(
+                sqlx::query_as::<_, ArchivedJobInfo>(stmt_info)
+                    .bind(job_id)
+                    .fetch_one(pool),
+                sqlx::query_as::<_, ArchivedLogRow>(stmt_log)
+                    .bind(job_id)
+                    .fetch_all(pool),
+            )?;
+            jobs.push(ArchivedJob { info, error_log });
+        }
+        Ok(Self { jobs })
+    }
+
+    /// 在同一事务中写入所有任务，任一任务已存在时整体回滚，返回新分配的任务 id
+    pub(crate) async fn store(&self, pool: &PgPool) -> Result<Vec<i64>> {
+        let mut job_ids = Vec::with_capacity(self.jobs.len());
+        let mut trans = pool.begin().await?;
+        for ArchivedJob { info, error_log } in &self.jobs {
+            let scheduler_id = info.scheduler_id.clone().unwrap_or_else(|| info.id.to_string());
+            let insert_job_info = sqlx::query_scalar(
+                "INSERT INTO job_info (scheduler, scheduler_id, array_job_id, array_index, name, queue, num_cpu, nodes, parameters, metadata) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, COALESCE($10, '{}')) RETURNING id;",
+            )
+                .bind(&info.scheduler)
+                .bind(scheduler_id)
+                .bind(&info.array_job_id)
+                .bind(info.array_index)
+                .bind(&info.name)
+                .bind(&info.queue)
+                .bind(info.num_cpu)
+                .bind(&info.nodes[..])
+                .bind(&info.parameters)
+                .bind(&info.metadata);
+            let job_id: i64 = insert_job_info.fetch_one(&mut *trans).await?;
+            job_ids.push(job_id);
+
+            let csv = error_log
+                .iter()
+                .map(|row| {
+                    format!(
+                        "{},{},{},{},{},{}\n",
+                        row.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+                        row.load,
+                        row.iter,
+                        row.error_u,
+                        row.error_phi,
+                        job_id
+                    )
+                })
+                .collect::<String>();
+            let mut stream = trans.copy_in_raw("COPY error_log (timestamp, load, iter, error_u, error_phi, job_id) FROM STDIN (FORMAT csv);").await?;
+            stream.send(csv.as_bytes()).await?;
+            stream.finish().await?;
+        }
+        refresh_job_summary(&mut trans, &job_ids).await?;
+        trans.commit().await?;
+
+        Ok(job_ids)
+    }
+}
+
+/// 将任务及其完整误差日志导出为归档文件
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn export_archive(
+    job_ids: Vec<i64>,
+    file: PathBuf,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let archive = {
+        let pool = pool.read().await;
+        Archive::fetch(pool.deref(), &job_ids).await?
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        archive.write_to(BufWriter::new(File::create(file)?))
+    })
+    .await?
+}
+
+/// 从归档文件导入任务，返回导入的任务 id
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn import_archive(file: PathBuf, pool: State<'_, RwLock<PgPool>>) -> Result<Vec<i64>> {
+    let archive = tauri::async_runtime::spawn_blocking(move || {
+        Archive::read_from(BufReader::new(File::open(file)?))
+    })
+    .await??;
+
+    let pool = pool.read().await;
+    archive.store(pool.deref()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn archive() -> Archive {
+        let timestamp = NaiveDate::from_ymd_opt(2023, 1, 1)
+            .and_then(|date| date.and_hms_milli_opt(10, 0, 0, 500))
+            .unwrap();
+        Archive {
+            jobs: vec![ArchivedJob {
+                info: ArchivedJobInfo {
+                    id: 666666,
+                    name: String::from("test_job"),
+                    queue: String::from("default"),
+                    num_cpu: 4,
+                    nodes: vec![String::from("node1"), String::from("node2")],
+                    parameters: Some(serde_json::json!({ "mesh": 1 })),
+                    scheduler: String::from("pbs"),
+                    scheduler_id: Some(String::from("666666.pbs01")),
+                    array_job_id: None,
+                    array_index: None,
+                    metadata: Some(serde_json::json!({ "user": "alice" })),
+                },
+                error_log: vec![ArchivedLogRow {
+                    timestamp,
+                    load: 1.5,
+                    iter: 1,
+                    error_u: 0.1,
+                    error_phi: 0.2,
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let mut buffer = Vec::new();
+        archive().write_to(&mut buffer).unwrap();
+        assert!(buffer.starts_with(Archive::MAGIC));
+
+        let restored = Archive::read_from(buffer.as_slice()).unwrap();
+        assert_eq!(format!("{:?}", restored), format!("{:?}", archive()));
+    }
+
+    #[test]
+    fn test_archive_rejects_invalid_header() {
+        assert!(Archive::read_from(&b"INSIGHT"[..]).is_err());
+        assert!(Archive::read_from(&b"NOTVALID\x01\x00"[..]).is_err());
+
+        let mut buffer = Vec::new();
+        archive().write_to(&mut buffer).unwrap();
+        buffer[8] = 0xff;
+        assert!(matches!(
+            Archive::read_from(buffer.as_slice()),
+            Err(Error::ArchiveFormat(_))
+        ));
+    }
+}
@@ -1,22 +1,203 @@
-use crate::config::AppConfig;
 use super::Result;
+use crate::commands::{Cache, ConnectionReport};
+use crate::config::{AppConfig, DatabaseConfig};
+use crate::error::{Error, Locale};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 use sqlx::PgPool;
 use tauri::State;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
+/// 修改配置的锁，读取、修改、保存配置并切换数据库期间持有，同时执行的命令不会丢失彼此的修改
+#[derive(Default)]
+pub struct ConfigLock(Mutex<()>);
+
+/// 在线程池中执行，访问系统凭据管理可能阻塞
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tauri::async_runtime::spawn_blocking(f).await?
+}
+
+/// 连接新的数据库，成功后再保存配置并调用 [`switch_database`]，连接失败时配置不变
+async fn connect(
+    (pool_options, connect_options): (PgPoolOptions, PgConnectOptions),
+) -> Result<PgPool> {
+    Ok(pool_options.connect_with(connect_options).await?)
+}
+
+/// 替换连接池并清空缓存，须在配置保存之后调用，保存失败时仍使用原来的数据库
+///
+/// 不同数据库中的任务 id 会重复，持有两把写锁期间完成替换，其它命令不会读到旧的缓存。
+/// 切换前已经开始的查询在结束后不会写入缓存，见 [`Cache::set_if_current`]。
+/// 旧的连接池不主动关闭，后台任务（如 [`purge_jobs`](super::purge_jobs)）持有的副本仍可使用，
+/// 最后一个副本释放时关闭连接。
+async fn switch_database(
+    new_pool: PgPool,
+    pool: &RwLock<PgPool>,
+    cache: &RwLock<Cache>,
+) -> Result<()> {
+    let mut pool = pool.write().await;
+    let mut cache = cache.write().await;
+    *pool = new_pool;
+    cache.clear();
+    Ok(())
+}
+
+/// 连接池与连接参数，读取密码可能阻塞
+fn database_options(database: &DatabaseConfig) -> Result<(PgPoolOptions, PgConnectOptions)> {
+    Ok((database.pool_options(), database.connect_options()?))
+}
+
+/// 设置错误提示的语言，`locale` 为前端的语言标签，如 `navigator.language`
+#[tauri::command]
+pub fn set_locale(locale: String) {
+    Locale::set(Locale::from_tag(&locale));
+}
+
+/// 读取配置，不返回密码，只标记凭据存储中是否已保存密码
 #[tauri::command]
-pub fn read_config() -> Result<AppConfig> {
-    AppConfig::load()
+pub async fn read_config() -> Result<AppConfig> {
+    blocking(|| {
+        let mut config = AppConfig::load()?;
+        for database in config.profiles.values_mut() {
+            database.check_password()?;
+        }
+        Ok(config)
+    })
+    .await
 }
 
-/// 配置写入文件
+/// 配置写入文件，密码保存到凭据存储，并切换到其中当前使用的数据库
 ///
 /// 建议调用时前端保证 `config` 与原始不同
 #[tauri::command]
-pub async fn write_config(config: AppConfig, pool: State<'_, RwLock<PgPool>>) -> Result<()> {
-    let mut pool = pool.write().await;
-    *pool = PgPool::connect(&config.database.url()).await?;
+pub async fn write_config(
+    config: AppConfig,
+    lock: State<'_, ConfigLock>,
+    pool: State<'_, RwLock<PgPool>>,
+    cache: State<'_, RwLock<Cache>>,
+) -> Result<()> {
+    let _guard = lock.0.lock().await;
+    let (config, options) = blocking(move || {
+        let mut config = config;
+        if let Err(errors) = config.check() {
+            return Err(Error::Config {
+                path: AppConfig::path()?,
+                errors,
+            });
+        }
+        for database in config.profiles.values_mut() {
+            database.store_password()?;
+        }
+        let options = database_options(config.database()?)?;
+        Ok((config, options))
+    })
+    .await?;
 
+    let new_pool = connect(options).await?;
     config.save()?;
-    Ok(())
-}
\ No newline at end of file
+    switch_database(new_pool, &pool, &cache).await
+}
+
+/// 添加数据库配置
+#[tauri::command]
+pub async fn add_profile(
+    name: String,
+    database: DatabaseConfig,
+    lock: State<'_, ConfigLock>,
+) -> Result<()> {
+    let _guard = lock.0.lock().await;
+    blocking(move || {
+        let mut config = AppConfig::load()?;
+        let mut database = database;
+        database.store_password()?;
+        config.add_profile(&name, database)?;
+        config.save()
+    })
+    .await
+}
+
+/// 修改数据库配置，修改当前使用的配置时重新连接
+#[tauri::command]
+pub async fn edit_profile(
+    name: String,
+    database: DatabaseConfig,
+    rename: Option<String>,
+    lock: State<'_, ConfigLock>,
+    pool: State<'_, RwLock<PgPool>>,
+    cache: State<'_, RwLock<Cache>>,
+) -> Result<()> {
+    let _guard = lock.0.lock().await;
+    let (config, options) = blocking(move || {
+        let mut config = AppConfig::load()?;
+        let mut database = database;
+        database.store_password()?;
+        let active = config.active == name;
+        config.edit_profile(&name, database, rename.as_deref())?;
+        let options = if active {
+            Some(database_options(config.database()?)?)
+        } else {
+            None
+        };
+        Ok((config, options))
+    })
+    .await?;
+
+    let new_pool = match options {
+        Some(options) => Some(connect(options).await?),
+        None => None,
+    };
+    config.save()?;
+    match new_pool {
+        Some(new_pool) => switch_database(new_pool, &pool, &cache).await,
+        None => Ok(()),
+    }
+}
+
+/// 删除数据库配置，不再被使用的密码一并从凭据存储中删除
+#[tauri::command]
+pub async fn remove_profile(name: String, lock: State<'_, ConfigLock>) -> Result<()> {
+    let _guard = lock.0.lock().await;
+    blocking(move || {
+        let mut config = AppConfig::load()?;
+        let secret = config.remove_profile(&name)?;
+        config.save()?;
+        if let Some(secret) = secret {
+            AppConfig::secrets()?.remove(&secret)?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// 测试已保存的数据库配置
+#[tauri::command]
+pub async fn test_profile(name: String) -> Result<ConnectionReport> {
+    let (pool_options, connect_options) = blocking(move || {
+        let config = AppConfig::load()?;
+        database_options(config.profiles.get(&name).ok_or(Error::ProfileNotFound(name))?)
+    })
+    .await?;
+
+    ConnectionReport::fetch(pool_options, connect_options).await
+}
+
+/// 切换当前使用的数据库配置
+#[tauri::command]
+pub async fn activate_profile(
+    name: String,
+    lock: State<'_, ConfigLock>,
+    pool: State<'_, RwLock<PgPool>>,
+    cache: State<'_, RwLock<Cache>>,
+) -> Result<()> {
+    let _guard = lock.0.lock().await;
+    let (config, options) = blocking(move || {
+        let mut config = AppConfig::load()?;
+        config.activate(&name)?;
+        let options = database_options(config.database()?)?;
+        Ok((config, options))
+    })
+    .await?;
+
+    let new_pool = connect(options).await?;
+    config.save()?;
+    switch_database(new_pool, &pool, &cache).await
+}
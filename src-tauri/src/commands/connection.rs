@@ -0,0 +1,234 @@
+use super::Result;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{PgPool, Row};
+use std::time::Instant;
+#[cfg(feature = "desktop")]
+use crate::config::DatabaseConfig;
+
+/// 应用用到的表与视图，见 `sql/up.sql`，其中由迁移脚本新增的见 [`MIGRATION_TABLES`]
+const TABLES: [&str; 10] = [
+    "job_info",
+    "job_summary",
+    "job_tags",
+    "job_notes",
+    "error_log",
+    "error_log_summary",
+    "load_steps",
+    "job_step_summary",
+    "modeling",
+    "modeling_jobs",
+];
+
+/// `sql/migrations` 中的迁移脚本，以及判断是否已执行的查询
+///
+/// 数据库中没有记录迁移历史，以脚本带来的结构变化判断。新增脚本时在此补充。
+/// 查询检查脚本的每一步，中途失败的脚本判断为未执行；脚本的每一步都须可重复执行，以便重新运行。
+/// 查询 `pg_attribute` 而不是 `information_schema`，后者不显示当前角色无权访问的列。
+const MIGRATIONS: [(&str, &str); 7] = [
+    (
+        "0001_modeling_name",
+        r#"
+    SELECT EXISTS (
+        SELECT FROM pg_attribute
+        WHERE attrelid = to_regclass('modeling') AND attname = 'name' AND NOT attisdropped
+    ) AND EXISTS (
+        SELECT FROM pg_attribute
+        WHERE attrelid = to_regclass('modeling') AND attname = 'id' AND attidentity <> ''
+    ) AND EXISTS (
+        SELECT FROM pg_constraint
+        WHERE conrelid = to_regclass('modeling_jobs') AND contype = 'p'
+    );"#,
+    ),
+    (
+        "0002_job_identity",
+        r#"
+    SELECT EXISTS (
+        SELECT FROM pg_attribute
+        WHERE attrelid = to_regclass('job_info') AND attname = 'scheduler_id' AND NOT attisdropped
+    ) AND to_regclass('job_info_scheduler_id_unique') IS NOT NULL
+    AND EXISTS (
+        SELECT FROM pg_attribute
+        WHERE attrelid = to_regclass('job_info') AND attname = 'id' AND attidentity <> ''
+    );"#,
+    ),
+    (
+        "0003_job_metadata",
+        r#"
+    SELECT EXISTS (
+        SELECT FROM pg_attribute
+        WHERE attrelid = to_regclass('job_info') AND attname = 'metadata' AND NOT attisdropped
+    );"#,
+    ),
+    (
+        "0004_job_annotations",
+        "SELECT to_regclass('job_notes') IS NOT NULL;",
+    ),
+    (
+        "0005_job_summary",
+        "SELECT to_regclass('job_summary') IS NOT NULL;",
+    ),
+    (
+        "0006_job_trash",
+        r#"
+    SELECT EXISTS (
+        SELECT FROM pg_attribute
+        WHERE attrelid = to_regclass('job_info') AND attname = 'deleted_at' AND NOT attisdropped
+    ) AND EXISTS (
+        SELECT FROM pg_attribute
+        WHERE attrelid = to_regclass('job_info') AND attname = 'purging' AND NOT attisdropped
+    );"#,
+    ),
+    (
+        "0007_load_steps",
+        "SELECT to_regclass('job_step_summary') IS NOT NULL;",
+    ),
+];
+
+/// 由迁移脚本新增的表，迁移尚未执行时不算缺失
+const MIGRATION_TABLES: [(&str, &str); 5] = [
+    ("job_summary", "0005_job_summary"),
+    ("load_steps", "0007_load_steps"),
+    ("job_step_summary", "0007_load_steps"),
+    ("job_tags", "0004_job_annotations"),
+    ("job_notes", "0004_job_annotations"),
+];
+
+/// 当前角色对某个表或视图的权限，表不存在时均为 `false`
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+pub struct TablePrivileges {
+    name: String,
+    exists: bool,
+    select: bool,
+    insert: bool,
+    update: bool,
+    delete: bool,
+}
+
+/// 测试数据库连接的结果
+#[derive(Debug, serde::Serialize)]
+pub struct ConnectionReport {
+    /// 如 `16.2 (Debian 16.2-1.pgdg120+2)`
+    server_version: String,
+    /// 建立连接的耗时（毫秒）
+    connect_time: f64,
+    /// 一次 `SELECT 1` 的往返耗时（毫秒）
+    latency: f64,
+    user: String,
+    database: String,
+    superuser: bool,
+    /// 能否在当前 schema 中建表，初始化数据库时需要
+    can_create: bool,
+    tables: Vec<TablePrivileges>,
+    /// 缺失的表与视图，为空表示已初始化
+    missing_tables: Vec<String>,
+    /// 尚未执行的迁移脚本
+    pending_migrations: Vec<String>,
+}
+
+impl ConnectionReport {
+    /// 建立单个连接并检查服务器、表结构与权限
+    pub async fn fetch(
+        pool_options: PgPoolOptions,
+        connect_options: PgConnectOptions,
+    ) -> Result<Self> {
+        let start = Instant::now();
+        let pool: PgPool = pool_options
+            .max_connections(1)
+            .min_connections(0)
+            .connect_with(connect_options)
+            .await?;
+        let connect_time = start.elapsed().as_secs_f64() * 1000.0;
+
+        let report = Self::inspect(&pool, connect_time).await;
+        pool.close().await;
+        report
+    }
+
+    async fn inspect(pool: &PgPool, connect_time: f64) -> Result<Self> {
+        let start = Instant::now();
+        sqlx::query("SELECT 1").execute(pool).await?;
+        let latency = start.elapsed().as_secs_f64() * 1000.0;
+
+        let stmt_role = r#"
+            SELECT
+                current_setting('server_version'),
+                current_user::TEXT,
+                current_database()::TEXT,
+                rolsuper,
+                has_schema_privilege(current_schema(), 'CREATE')
+            FROM pg_roles
+            WHERE rolname = current_user;"#;
+        let row = sqlx::query(stmt_role).fetch_one(pool).await?;
+
+        // 表不存在时 `to_regclass` 为 NULL，`has_table_privilege` 也为 NULL
+        let stmt_tables = r#"
+            SELECT
+                name,
+                to_regclass(name) IS NOT NULL AS "exists",
+                COALESCE(has_table_privilege(to_regclass(name)::OID, 'SELECT'), FALSE) AS "select",
+                COALESCE(has_table_privilege(to_regclass(name)::OID, 'INSERT'), FALSE) AS "insert",
+                COALESCE(has_table_privilege(to_regclass(name)::OID, 'UPDATE'), FALSE) AS "update",
+                COALESCE(has_table_privilege(to_regclass(name)::OID, 'DELETE'), FALSE) AS "delete"
+            FROM unnest($1::TEXT[]) AS t(name);"#;
+        let tables: Vec<TablePrivileges> = sqlx::query_as(stmt_tables)
+            .bind(&TABLES[..])
+            .fetch_all(pool)
+            .await?;
+        let migration = |name: &str| {
+            MIGRATION_TABLES
+                .iter()
+                .find(|(table, _)| *table == name)
+                .map(|(_, migration)| *migration)
+        };
+
+        // 未初始化时迁移无从谈起
+        let initialized = tables
+            .iter()
+            .all(|table| table.exists || migration(&table.name).is_some());
+        let mut pending_migrations = Vec::new();
+        if initialized {
+            for (name, stmt) in MIGRATIONS {
+                let applied: bool = sqlx::query_scalar(stmt).fetch_one(pool).await?;
+                if !applied {
+                    pending_migrations.push(name.to_owned());
+                }
+            }
+        }
+        let missing_tables = tables
+            .iter()
+            .filter(|table| !table.exists)
+            .filter(|table| match migration(&table.name) {
+                Some(migration) => !pending_migrations.iter().any(|name| name == migration),
+                None => true,
+            })
+            .map(|table| table.name.clone())
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            server_version: row.try_get(0)?,
+            connect_time,
+            latency,
+            user: row.try_get(1)?,
+            database: row.try_get(2)?,
+            superuser: row.try_get(3)?,
+            can_create: row.try_get(4)?,
+            tables,
+            missing_tables,
+            pending_migrations,
+        })
+    }
+}
+
+/// 测试数据库配置，可以是尚未保存的
+///
+/// `config` 中密码为空时使用 `password_secret` 对应的已保存密码。
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn test_connection(config: DatabaseConfig) -> Result<ConnectionReport> {
+    // 读取密码可能阻塞
+    let (pool_options, connect_options) = tauri::async_runtime::spawn_blocking(move || {
+        Result::Ok((config.pool_options(), config.connect_options()?))
+    })
+    .await??;
+    ConnectionReport::fetch(pool_options, connect_options).await
+}
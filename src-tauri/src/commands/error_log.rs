@@ -1,7 +1,10 @@
 use crate::error::Error;
 
-use super::Result;
+use super::{median, Result, SchedulerId};
 use ahash::AHashMap;
+use arrow::array::{ArrayRef, BooleanArray, Float64Array, Int32Array, Int64Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use chrono::NaiveDateTime;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
@@ -9,40 +12,180 @@ use lazy_static::lazy_static;
 use rayon::iter::ParallelIterator;
 use rayon::str::ParallelString;
 use regex::Regex;
-use sqlx::{Executor, PgPool, Row};
+use serde_json::{Map, Value};
+use sqlx::{PgConnection, PgPool};
 use std::collections::VecDeque;
 use std::fs;
-use std::io::{Read, Write};
-use std::ops::Deref;
-use std::path::PathBuf;
-use tauri::{AppHandle, Manager, State};
-use tokio::sync::RwLock;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "desktop")]
+use {
+    super::{ipc_message, Transport, MSGPACK_CHUNK_ROWS},
+    futures_util::TryStreamExt,
+    std::future::{ready, Future},
+    sqlx::Row,
+    std::ops::Deref,
+    std::path::PathBuf,
+    tauri::{AppHandle, Manager, State},
+    tokio::sync::RwLock,
+};
 
-#[derive(Debug, serde::Serialize, sqlx::FromRow)]
-pub(crate) struct ErrorLogEntry {
-    iters: i32,
-    load: f64,
-    error_u: f64,
-    error_phi: f64,
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+pub struct ErrorLogEntry {
+    pub(crate) iters: i32,
+    /// 日志中的迭代计数，重试时重新计数，用于划分加载步
+    #[serde(skip)]
+    pub(crate) iter: i32,
+    pub(crate) load: f64,
+    pub(crate) error_u: f64,
+    pub(crate) error_phi: f64,
+    /// 与上一次迭代的时间间隔（秒），第一次迭代为 `None`
+    pub(crate) elapsed: Option<f64>,
+    /// 耗时远超所在加载步中位数的迭代，通常由 I/O 阻塞或重建预条件子导致
+    #[sqlx(default)]
+    pub(crate) outlier: bool,
 }
 
-#[derive(Debug, serde::Serialize, sqlx::FromRow)]
-pub(crate) struct ErrorLogSummary {
-    load: f64,
-    iters: i32,
-    cost: Option<f64>,
+impl ErrorLogEntry {
+    /// 单次迭代耗时超过所在加载步中位数的倍数时视为异常
+    const OUTLIER_FACTOR: f64 = 5.0;
+    /// 视为异常的最短耗时（秒），避免中位数接近 0 时误报
+    const OUTLIER_MIN_ELAPSED: f64 = 1.0;
+
+    /// 两次相邻的迭代是否属于同一加载步，与 `load_steps` 视图的划分相同：
+    /// 载荷变化或迭代次数重新计数时开始新的加载步
+    fn same_step(&self, next: &Self) -> bool {
+        self.load == next.load && next.iter > self.iter
+    }
+
+    /// 按加载步标记耗时异常的迭代，`entries` 须按时间排序
+    fn mark_outliers(entries: &mut [ErrorLogEntry]) {
+        for step in entries.chunk_by_mut(Self::same_step) {
+            let Some(median) = median(step.iter().filter_map(|entry| entry.elapsed).collect())
+            else {
+                continue;
+            };
+            let threshold = (median * Self::OUTLIER_FACTOR).max(Self::OUTLIER_MIN_ELAPSED);
+            for entry in step {
+                entry.outlier = entry.elapsed.is_some_and(|elapsed| elapsed > threshold);
+            }
+        }
+    }
+
+    /// 构建 Arrow 表，`rows` 为 `(job_id, entry)`
+    pub(crate) fn record_batch<'a>(
+        rows: impl Iterator<Item = (i64, &'a Self)> + Clone,
+    ) -> Result<RecordBatch> {
+        let schema = Schema::new(vec![
+            Field::new("job_id", DataType::Int64, false),
+            Field::new("iters", DataType::Int32, false),
+            Field::new("load", DataType::Float64, false),
+            Field::new("error_u", DataType::Float64, false),
+            Field::new("error_phi", DataType::Float64, false),
+            Field::new("elapsed", DataType::Float64, true),
+            Field::new("outlier", DataType::Boolean, false),
+        ]);
+        let entries = || rows.clone().map(|(_, entry)| entry);
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(rows.clone().map(|(job_id, _)| job_id).collect::<Int64Array>()),
+            Arc::new(entries().map(|e| e.iters).collect::<Int32Array>()),
+            Arc::new(entries().map(|e| e.load).collect::<Float64Array>()),
+            Arc::new(entries().map(|e| e.error_u).collect::<Float64Array>()),
+            Arc::new(entries().map(|e| e.error_phi).collect::<Float64Array>()),
+            Arc::new(entries().map(|e| e.elapsed).collect::<Float64Array>()),
+            Arc::new(entries().map(|e| Some(e.outlier)).collect::<BooleanArray>()),
+        ];
+        Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+pub struct ErrorLogSummary {
+    pub(crate) load: f64,
+    pub(crate) iters: i32,
+    pub(crate) cost: Option<f64>,
+}
+
+impl ErrorLogSummary {
+    /// 构建 Arrow 表，`rows` 为 `(job_id, summary)`
+    pub(crate) fn record_batch<'a>(
+        rows: impl Iterator<Item = (i64, &'a Self)> + Clone,
+    ) -> Result<RecordBatch> {
+        let schema = Schema::new(vec![
+            Field::new("job_id", DataType::Int64, false),
+            Field::new("load", DataType::Float64, false),
+            Field::new("iters", DataType::Int32, false),
+            Field::new("cost", DataType::Float64, true),
+        ]);
+        let summaries = || rows.clone().map(|(_, summary)| summary);
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(rows.clone().map(|(job_id, _)| job_id).collect::<Int64Array>()),
+            Arc::new(summaries().map(|s| s.load).collect::<Float64Array>()),
+            Arc::new(summaries().map(|s| s.iters).collect::<Int32Array>()),
+            Arc::new(summaries().map(|s| s.cost).collect::<Float64Array>()),
+        ];
+        Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+    }
+}
+
+/// 按时间顺序排列的单个加载步，见 `sql/up.sql` 中的 `load_steps` 视图
+///
+/// 载荷变化或迭代次数重新计数时开始新的加载步，同一载荷的重试是两个加载步。
+/// 若下一个加载步的载荷不大于当前载荷（即发生了回退或重试），则认为当前加载步不收敛。
+/// 任务的最后一个加载步没有后续可供判断，视为收敛。
+#[derive(Debug, sqlx::FromRow)]
+pub(crate) struct LoadStep {
+    pub(crate) job_id: i64,
+    pub(crate) load: f64,
+    pub(crate) iters: i32,
+    /// 距下一个加载步开始的秒数，最后一个加载步为其自身持续时间
+    pub(crate) cost: f64,
+    pub(crate) converged: bool,
+}
+
+/// 查询多个任务的 [`LoadStep`]，参数 `$1` 为任务 id 数组
+pub(crate) const LOAD_STEP_STMT: &str = r#"
+    SELECT job_id, load, iters, cost, converged
+    FROM load_steps
+    WHERE job_id = ANY($1)
+    ORDER BY job_id, step;"#;
+
+/// 重新计算任务的汇总指标，参数 `$1` 为任务 id 数组，见 `sql/up.sql` 中的 `job_step_summary` 视图
+const JOB_SUMMARY_STMT: &str = r#"
+    INSERT INTO job_summary (job_id, iterations, load_steps, failed_steps, final_load, wall_time)
+    SELECT job_id, iterations, load_steps, failed_steps, final_load, wall_time
+    FROM job_step_summary
+    WHERE job_id = ANY($1)
+    ON CONFLICT (job_id) DO UPDATE SET
+        iterations = EXCLUDED.iterations,
+        load_steps = EXCLUDED.load_steps,
+        failed_steps = EXCLUDED.failed_steps,
+        final_load = EXCLUDED.final_load,
+        wall_time = EXCLUDED.wall_time;"#;
+
+/// 写入误差日志后更新任务的汇总指标，与写入在同一事务中执行
+///
+/// 只扫描这些任务的误差日志，查询任务列表时直接读取 `job_summary`。
+pub(crate) async fn refresh_job_summary(conn: &mut PgConnection, job_ids: &[i64]) -> Result<()> {
+    sqlx::query(JOB_SUMMARY_STMT).bind(job_ids).execute(conn).await?;
+    Ok(())
 }
 
 /// 缓存结构体，用于存储压缩后的日志数据。
 ///
 /// 该缓存使用 LRU（最近最少使用）策略来管理缓存项，
-/// 并使用 Gzip 压缩/解压数据以减少内存占用。
+/// 每项为 [`MessageWriter`] 压缩的一组消息，读取时用 [`Messages`] 逐条解压。
+#[cfg_attr(not(feature = "desktop"), allow(dead_code))]
 pub struct Cache {
-    map: AHashMap<i64, Vec<u8>>,
+    map: AHashMap<i64, Arc<[u8]>>,
     queue: VecDeque<i64>,
     max_size: usize,
+    /// 每次清空后递增，见 [`set_if_current`](Self::set_if_current)
+    generation: u64,
 }
 
+#[cfg_attr(not(feature = "desktop"), allow(dead_code))]
 impl Cache {
     pub fn new() -> Self {
         const MAX_SIZE: usize = 8;
@@ -50,38 +193,44 @@ impl Cache {
             queue: VecDeque::with_capacity(MAX_SIZE),
             max_size: MAX_SIZE,
             map: AHashMap::new(),
+            generation: 0,
         }
     }
 
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
     pub fn has(&self, key: i64) -> bool {
         self.map.contains_key(&key)
     }
 
-    pub fn get(&self, key: i64) -> Option<Vec<u8>> {
-        self.map.get(&key).and_then(|item| {
-            let mut decoder = GzDecoder::new(item.as_slice());
-            let mut buffer = Vec::new();
-            match decoder.read_to_end(&mut buffer) {
-                Ok(_) => Some(buffer),
-                Err(_) => None,
-            }
-        })
+    /// 返回逐条解压的消息，不持有缓存的锁
+    pub fn get(&self, key: i64) -> Option<Messages> {
+        self.map.get(&key).map(|item| Messages(GzDecoder::new(Cursor::new(item.clone()))))
     }
 
-    pub fn set(&mut self, key: i64, value: &[u8]) -> Result<()> {
+    /// 写入 [`MessageWriter::finish`] 压缩好的消息
+    pub fn set(&mut self, key: i64, value: Vec<u8>) {
         if self.has(key) {
-            return Ok(());
+            return;
         }
         if self.map.len() >= self.max_size {
-            let outdated_key = self.queue.pop_front().unwrap();
-            self.map.remove(&outdated_key);
+            if let Some(outdated_key) = self.queue.pop_front() {
+                self.map.remove(&outdated_key);
+            }
         }
-        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-        encoder.write_all(value)?;
-        self.map.insert(key, encoder.finish()?);
+        self.map.insert(key, value.into());
         self.queue.push_back(key);
+    }
 
-        Ok(())
+    /// 仅当缓存在读取 `generation` 之后没有清空时写入
+    ///
+    /// 切换数据库时会清空缓存，此前开始的查询结果来自旧的数据库，不能写入。
+    pub fn set_if_current(&mut self, generation: u64, key: i64, value: Vec<u8>) {
+        if generation == self.generation {
+            self.set(key, value);
+        }
     }
 
     pub fn remove(&mut self, key: i64) {
@@ -94,66 +243,97 @@ impl Cache {
     pub fn clear(&mut self) {
         self.map.clear();
         self.queue.clear();
+        self.generation += 1;
+    }
+}
+
+/// 边发送边压缩写入缓存的消息，每条消息前为 4 字节小端序的长度
+#[cfg_attr(not(feature = "desktop"), allow(dead_code))]
+pub struct MessageWriter(GzEncoder<Vec<u8>>);
+
+#[cfg_attr(not(feature = "desktop"), allow(dead_code))]
+impl MessageWriter {
+    pub fn new() -> Self {
+        Self(GzEncoder::new(Vec::new(), Compression::default()))
+    }
+
+    pub fn write(&mut self, message: &[u8]) -> Result<()> {
+        let len = u32::try_from(message.len())
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+        self.0.write_all(&len.to_le_bytes())?;
+        Ok(self.0.write_all(message)?)
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>> {
+        Ok(self.0.finish()?)
+    }
+}
+
+/// 按写入顺序逐条解压缓存的消息
+pub struct Messages(GzDecoder<Cursor<Arc<[u8]>>>);
+
+impl Iterator for Messages {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut len = [0; 4];
+        match self.0.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(e.into())),
+        }
+        let mut message = vec![0; u32::from_le_bytes(len) as usize];
+        Some(self.0.read_exact(&mut message).map(|_| message).map_err(Error::from))
     }
 }
 
 lazy_static! {
     static ref TIMESTAMP_PATTERN: Regex = Regex::new(r"(\d{4})-(\d{2})-(\d{2}) (\d{2}):(\d{2}):(\d{2})").unwrap();
     static ref LOG_PATTERN: Regex = Regex::new(r"(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d{3}).*?l=([\d.e+-]+).*?iter=(\d+).*?err=\{ u=([\d.e+-]+) phi=([\d.e+-]+)").unwrap();
-    static ref JOB_INFO_PATTERN: Regex = Regex::new(r"JobInfo\(.*?\bid='([^']*)'.*?\bname='([^']*)'.*?\bqueue='([^']*)'.*?\bn=(\d+).*?\bnodes=\[(.*)\].*\)").unwrap();
     static ref PARAMS_PATTERN: Regex = Regex::new(r"\{.*\}").unwrap();
 }
 
-struct LogParser;
+pub struct LogParser;
 
 impl LogParser {
-    /// 该函数用于解析包含作业信息和误差日志的文本内容，提取出结构化的作业信息
-    /// 并将日志数据转换为CSV格式以便后续导入数据库。
+    /// 解析日志的前两行：作业信息（[`JobHeader`]）与参数，返回作业信息与其后的误差日志
     ///
     /// # 日志格式要求
     ///
     /// 输入的日志内容应遵循特定格式：
-    /// 1. 第一行为作业信息（[`JobInfo`]），格式如：`JobInfo(id='...', name='...', queue='...', n=..., nodes=[...])`
+    /// 1. 第一行为作业信息，格式如：`JobInfo(id='...', name='...', queue='...', n=..., nodes=[...])`，
+    ///    可选的 `scheduler='...'` 注明调度系统，作业号格式见 [`SchedulerId`]；
+    ///    其余字段（如 `user`、`walltime`、`exit_status`）连同以上字段一并保存在 `metadata` 中，见 [`HeaderFields`]
     /// 2. 第二行为参数信息（JSON格式字符串，可选）
     /// 3. 后续行为具体的日志条目，每行包含时间戳、加载步、单步迭代次数、迭代误差等信息
     ///
-    /// # 返回值
-    ///
-    /// 返回一个Result元组：
-    /// * `Ok((JobInfo, String))`: 成功时返回解析出的作业详细信息结构体和转换后的CSV格式日志字符串
-    /// * `Err(...)`: 解析失败时返回错误信息
-    ///
-    /// CSV格式的日志数据包含以下列：
-    /// timestamp, load, iter, error_u, error_phi, job_id
-    ///
     /// # 示例
     ///
     /// ```text
-    /// JobInfo(id='666666', name='test_job', queue='default', n=4, nodes=['node1', 'node2'])
+    /// JobInfo(id='666666.pbs01', name='test_job', queue='default', n=4, nodes=['node1', 'node2'])
     /// {param1: value1, param2: value2}
     /// 2023-01-01 10:00:00.000 ... l=1.5 ... iter=1 ... err={ u=0.1 phi=0.2 }
     /// 2023-01-01 10:01:00.000 ... l=1.2 ... iter=2 ... err={ u=0.05 phi=0.15 }
     /// ```
     ///
-    /// 将被解析为：
+    /// 作业信息将被解析为：
     ///
     /// ```text
-    /// JobInfo { id: "666666", name: "test_job", queue: "default", n: 4, nodes: ["node1", "node2"], parameters: Some("{param1: value1, param2: value2}") }
+    /// JobHeader { id: SchedulerId { scheduler: "pbs", scheduler_id: "666666.pbs01", .. }, name: "test_job", queue: "default", n: 4, nodes: ["node1", "node2"], parameters: Some("{param1: value1, param2: value2}"), metadata: {"id": "666666.pbs01", ...} }
     /// ```
-    ///
-    /// 和CSV数据：
-    ///
-    /// ```csv
-    /// 2023-01-01 10:00:00.000,1.5,1,0.1,0.2,666666
-    /// 2023-01-01 10:01:00.000,1.2,2,0.05,0.15,666666
-    /// ```
-    fn parse_to_csv(logs: &str) -> Result<(JobInfo, String)> {
-        let (job_info_str, remaining) = logs
-            .split_once('\n')
-            .ok_or(Error::LogFormat(String::from("格式错误：第一行附近")))?;
-        let (params_str, remaining) = remaining
-            .split_once('\n')
-            .ok_or(Error::LogFormat(String::from("格式错误：第二行附近")))?;
+    fn parse_header(logs: &str) -> Result<(JobHeader, &str)> {
+        // 空文件没有可以指出的行
+        if logs.trim().is_empty() {
+            return Err(Error::LogFormat(String::from("empty log")));
+        }
+        let (job_info_str, remaining) = logs.split_once('\n').ok_or(Error::LogLine {
+            line: 2,
+            reason: String::from("missing parameters line"),
+        })?;
+        let (params_str, remaining) = remaining.split_once('\n').ok_or(Error::LogLine {
+            line: 3,
+            reason: String::from("missing error log"),
+        })?;
 
         // 解析参数
         let parameters = PARAMS_PATTERN
@@ -161,161 +341,608 @@ impl LogParser {
             .map(|mat| mat.as_str().to_owned());
 
         // 解析作业信息
-        let job_info = JOB_INFO_PATTERN
-            .captures(job_info_str)
-            .map(|cap| JobInfo {
-                id: cap[1].parse().ok().unwrap(),
-                name: cap[2].to_owned(),
-                queue: cap[3].to_owned(),
-                n: cap[4].parse().ok().unwrap(),
-                nodes: cap[5]
-                    .split(',')
-                    .map(|s| s.trim_matches(&[' ', '\'']).to_owned())
-                    .collect(),
-                parameters,
-            })
-            .ok_or(Error::LogFormat(String::from("Cannot parse job info")))?;
+        let header_error = |reason: String| Error::LogLine { line: 1, reason };
+        let metadata = HeaderFields::parse(job_info_str).map_err(header_error)?;
+        let field = |name: &str| {
+            metadata
+                .get(name)
+                .ok_or_else(|| header_error(format!("missing field '{name}'")))
+        };
+        let text = |name: &str| match field(name)? {
+            Value::String(s) => Ok(s.clone()),
+            Value::Number(n) => Ok(n.to_string()),
+            value => Err(header_error(format!("field '{name}' should be a string, got {value}"))),
+        };
+        let scheduler = metadata.get("scheduler").and_then(Value::as_str);
+        let id = SchedulerId::parse(&text("id")?, scheduler).map_err(header_error)?;
+        let n = field("n")?;
+        let n = n
+            .as_i64()
+            .and_then(|n| i32::try_from(n).ok())
+            .ok_or_else(|| header_error(format!("invalid CPU count {n}")))?;
+        let nodes = match field("nodes")? {
+            Value::Array(nodes) => nodes
+                .iter()
+                .map(|node| match node {
+                    Value::String(s) => s.clone(),
+                    node => node.to_string(),
+                })
+                .collect(),
+            Value::String(nodes) => nodes.split(',').map(|s| s.trim().to_owned()).collect(),
+            value => {
+                return Err(header_error(format!("field 'nodes' should be a list, got {value}")))
+            }
+        };
+        let job_info = JobHeader {
+            id,
+            name: text("name")?,
+            queue: text("queue")?,
+            n,
+            nodes,
+            parameters,
+            metadata,
+        };
+
+        Ok((job_info, remaining))
+    }
 
-        // 根据误差日志第一行提取
-        let indices = LOG_PATTERN
-            .captures(logs)
-            .map(|cap| {
-                cap.iter()
+    /// 将误差日志转换为CSV格式以便导入数据库
+    ///
+    /// CSV格式的日志数据包含以下列：
+    /// timestamp, load, iter, error_u, error_phi, job_id
+    ///
+    /// 如 [`LogParser::parse_header`] 中的示例将转换为：
+    ///
+    /// ```csv
+    /// 2023-01-01 10:00:00.000,1.5,1,0.1,0.2,42
+    /// 2023-01-01 10:01:00.000,1.2,2,0.05,0.15,42
+    /// ```
+    fn entries_to_csv(logs: &str, job_id: i64) -> Result<String> {
+        // 根据误差日志第一行提取，索引相对于行首
+        let (line_len, indices) = logs
+            .lines()
+            .find_map(|line| LOG_PATTERN.captures(line).map(|cap| (line.len(), cap)))
+            .map(|(line_len, cap)| {
+                let indices = cap
+                    .iter()
                     .skip(1)
                     .flatten()
                     .map(|m| m.range())
-                    .collect::<Vec<_>>()
+                    .collect::<Vec<_>>();
+                (line_len, indices)
             })
             .ok_or(Error::LogFormat(String::from("Cannot build index")))?;
 
         // 利用索引提取字段，构建CSV
-        let csv = remaining
+        let csv = logs
             .par_lines()
             .filter_map(|line| {
-                LOG_PATTERN.is_match(line).then(|| {
-                    let mut row = indices
-                        .iter()
-                        .cloned()
-                        .map(|range| &line[range])
-                        .collect::<Vec<_>>()
-                        .join(",");
-                    row.push_str(&format!(",{}\n", job_info.id));
-                    row
-                })
+                if !LOG_PATTERN.is_match(line) {
+                    return None;
+                }
+                // 定宽日志的各行等长，可直接按索引截取；长度不同说明布局变化，改为逐行匹配
+                let fields = (line.len() == line_len)
+                    .then(|| {
+                        indices
+                            .iter()
+                            .map(|range| line.get(range.clone()))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .flatten()
+                    .or_else(|| {
+                        LOG_PATTERN.captures(line).map(|cap| {
+                            cap.iter().skip(1).flatten().map(|m| m.as_str()).collect()
+                        })
+                    })?;
+                let mut row = fields.join(",");
+                row.push_str(&format!(",{job_id}\n"));
+                Some(row)
             })
             .collect::<String>();
 
-        Ok((job_info, csv))
+        Ok(csv)
+    }
+
+    /// 解析日志内容，误差日志按列返回，便于构建 DataFrame
+    ///
+    /// 与导入数据库使用同一套解析逻辑，结果与 [`LogParser::entries_to_csv`] 一致。
+    pub fn parse(logs: &str) -> Result<ParsedLog> {
+        let (header, remaining) = Self::parse_header(logs)?;
+        let csv = Self::entries_to_csv(remaining, 0)?;
+        let mut columns = LogColumns::default();
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(csv.as_bytes());
+        for record in reader.deserialize::<(String, f64, i32, f64, f64)>() {
+            let (timestamp, load, iter, error_u, error_phi) = record?;
+            let timestamp = NaiveDateTime::parse_from_str(&timestamp, "%Y-%m-%d %H:%M:%S%.f")
+                .map_err(|e| Error::LogFormat(format!("invalid timestamp '{timestamp}': {e}")))?;
+            columns.timestamp.push(timestamp);
+            columns.load.push(load);
+            columns.iter.push(iter);
+            columns.error_u.push(error_u);
+            columns.error_phi.push(error_phi);
+        }
+        Ok(ParsedLog { header, columns })
+    }
+}
+
+/// 作业信息行 `JobInfo(key=value, ...)` 中的字段
+///
+/// 值的写法与 Python 的 `repr` 相同：带引号的字符串、整数、浮点数、`True`/`False`/`None`，
+/// 以及由这些值组成的列表 `[...]`；其它未加引号的值按字符串保存，字典等括号内的值保留原文。
+struct HeaderFields<'a> {
+    rest: &'a str,
+}
+
+impl<'a> HeaderFields<'a> {
+    /// 列表嵌套的最大层数，更深的按原文保存
+    const MAX_DEPTH: usize = 8;
+
+    fn parse(line: &'a str) -> std::result::Result<Map<String, Value>, String> {
+        const PREFIX: &str = "JobInfo(";
+        let start = line
+            .find(PREFIX)
+            .map(|start| start + PREFIX.len())
+            .ok_or("expected JobInfo(id='...', name='...', queue='...', n=..., nodes=[...])")?;
+        let end = line
+            .rfind(')')
+            .filter(|&end| end >= start)
+            .ok_or("missing ')' after JobInfo fields")?;
+
+        let mut fields = Self {
+            rest: &line[start..end],
+        };
+        let mut map = Map::new();
+        loop {
+            fields.skip_separators();
+            if fields.rest.is_empty() {
+                return Ok(map);
+            }
+            let key = fields.key()?;
+            let value = fields.value(0)?;
+            map.insert(key.to_owned(), value);
+        }
+    }
+
+    /// 出错位置附近的内容
+    fn near(&self) -> String {
+        self.rest.chars().take(20).collect()
+    }
+
+    fn skip_separators(&mut self) {
+        self.rest = self
+            .rest
+            .trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+    }
+
+    fn key(&mut self) -> std::result::Result<&'a str, String> {
+        let len = self
+            .rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest.len());
+        let (key, rest) = self.rest.split_at(len);
+        match rest.trim_start().strip_prefix('=') {
+            Some(rest) if !key.is_empty() => {
+                self.rest = rest.trim_start();
+                Ok(key)
+            }
+            _ => Err(format!("expected key=value near '{}'", self.near())),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> std::result::Result<Value, String> {
+        match self.rest.chars().next() {
+            Some(quote @ ('\'' | '"')) => self.quoted(quote).map(Value::String),
+            Some('[') if depth < Self::MAX_DEPTH => self.list(depth),
+            Some(open @ ('{' | '(')) => self.group(open).map(Value::String),
+            _ => self.bare(),
+        }
+    }
+
+    fn quoted(&mut self, quote: char) -> std::result::Result<String, String> {
+        let mut value = String::new();
+        let mut chars = self.rest.char_indices().skip(1);
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, c)) => value.push(c),
+                    None => break,
+                },
+                c if c == quote => {
+                    self.rest = &self.rest[i + c.len_utf8()..];
+                    return Ok(value);
+                }
+                c => value.push(c),
+            }
+        }
+        Err(format!("unterminated string near '{}'", self.near()))
+    }
+
+    fn list(&mut self, depth: usize) -> std::result::Result<Value, String> {
+        let start = self.near();
+        self.rest = &self.rest[1..];
+        let mut items = Vec::new();
+        loop {
+            self.skip_separators();
+            if let Some(rest) = self.rest.strip_prefix(']') {
+                self.rest = rest;
+                return Ok(Value::Array(items));
+            }
+            if self.rest.is_empty() {
+                return Err(format!("unterminated list near '{start}'"));
+            }
+            items.push(self.value(depth + 1)?);
+        }
+    }
+
+    /// 括号内的原文，包括括号本身
+    fn group(&mut self, open: char) -> std::result::Result<String, String> {
+        let close = if open == '{' { '}' } else { ')' };
+        let mut level = 0usize;
+        for (i, c) in self.rest.char_indices() {
+            if c == open {
+                level += 1;
+            } else if c == close {
+                level -= 1;
+                if level == 0 {
+                    let (group, rest) = self.rest.split_at(i + c.len_utf8());
+                    self.rest = rest;
+                    return Ok(group.to_owned());
+                }
+            }
+        }
+        Err(format!("unbalanced '{open}' near '{}'", self.near()))
+    }
+
+    fn bare(&mut self) -> std::result::Result<Value, String> {
+        let len = self.rest.find([',', ']']).unwrap_or(self.rest.len());
+        let (text, rest) = self.rest.split_at(len);
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(format!("missing value near '{}'", self.near()));
+        }
+        self.rest = rest;
+
+        Ok(match text {
+            "True" => Value::Bool(true),
+            "False" => Value::Bool(false),
+            "None" => Value::Null,
+            _ => text
+                .parse::<i64>()
+                .map(Value::from)
+                .ok()
+                .or_else(|| {
+                    text.parse::<f64>()
+                        .ok()
+                        .and_then(serde_json::Number::from_f64)
+                        .map(Value::Number)
+                })
+                .unwrap_or_else(|| Value::String(text.to_owned())),
+        })
     }
 }
 
+/// [`LogParser::parse`] 的结果
 #[derive(Debug)]
-struct JobInfo {
-    id: String,
+pub struct ParsedLog {
+    pub header: JobHeader,
+    pub columns: LogColumns,
+}
+
+/// 按列存储的误差日志
+#[derive(Debug, Default)]
+pub struct LogColumns {
+    pub timestamp: Vec<NaiveDateTime>,
+    pub load: Vec<f64>,
+    pub iter: Vec<i32>,
+    pub error_u: Vec<f64>,
+    pub error_phi: Vec<f64>,
+}
+
+/// 日志第一行记录的作业信息
+#[derive(Debug, serde::Serialize)]
+pub struct JobHeader {
+    #[serde(flatten)]
+    id: SchedulerId,
     name: String,
     queue: String,
     n: i32,
     nodes: Vec<String>,
     parameters: Option<String>,
+    /// 作业信息行中的全部字段
+    metadata: Map<String, Value>,
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn import_error_log(file: PathBuf, pool: State<'_, RwLock<PgPool>>) -> Result<i64> {
+    let pool = pool.read().await;
+    import_log_file(pool.deref(), &file).await
+}
+
+/// 读取并导入日志文件，返回任务 id
+pub(crate) async fn import_log_file(pool: &PgPool, file: &Path) -> Result<i64> {
     let content = fs::read_to_string(file)?;
-    let (job_info, logs) = LogParser::parse_to_csv(&content)?;
+    import_log(pool, &content).await
+}
 
-    let insert_job_info = sqlx::query(
-        "INSERT INTO job_info (id, name, queue, num_cpu, nodes, parameters) VALUES ($1::bigint, $2, $3, $4, $5, $6::jsonb);",
+/// 解析日志内容并在同一事务中写入作业信息、误差日志与汇总指标，返回数据库分配的任务 id
+///
+/// 同一调度系统的作业号已存在时返回唯一约束错误。
+pub(crate) async fn import_log(pool: &PgPool, content: &str) -> Result<i64> {
+    let (job_info, remaining) = LogParser::parse_header(content)?;
+
+    let insert_job_info = sqlx::query_scalar(
+        "INSERT INTO job_info (scheduler, scheduler_id, array_job_id, array_index, name, queue, num_cpu, nodes, parameters, metadata) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9::jsonb, $10) RETURNING id;",
     )
-        .bind(&job_info.id)
+        .bind(&job_info.id.scheduler)
+        .bind(&job_info.id.scheduler_id)
+        .bind(&job_info.id.array_job_id)
+        .bind(job_info.id.array_index)
         .bind(&job_info.name)
         .bind(&job_info.queue)
-        .bind(&job_info.n)
+        .bind(job_info.n)
         .bind(&job_info.nodes[..])
-        .bind(&job_info.parameters);
+        .bind(&job_info.parameters)
+        .bind(sqlx::types::Json(&job_info.metadata));
 
-    let pool = pool.read().await;
     let mut trans = pool.begin().await?;
-    trans.execute(insert_job_info).await?;
+    let job_id: i64 = insert_job_info.fetch_one(&mut *trans).await?;
+    let logs = LogParser::entries_to_csv(remaining, job_id)?;
     let mut stream = trans.copy_in_raw("COPY error_log (timestamp, load, iter, error_u, error_phi, job_id) FROM STDIN (FORMAT csv);").await?;
     stream.send(logs.as_bytes()).await?;
     stream.finish().await?;
+    refresh_job_summary(&mut trans, &[job_id]).await?;
     trans.commit().await?;
 
-    job_info
-        .id
-        .parse()
-        .map_err(|_| Error::LogFormat(String::from("job id is not a number")))
+    Ok(job_id)
 }
 
+/// 发送加载步汇总与误差日志
+///
+/// 默认分块发送 MessagePack 消息，每条消息为 `[summary, entries]`，
+/// 完整的 `summary` 只在第一条消息中，详见 [`error_log_messages`]；
+/// 使用 [`Transport::Arrow`] 时依次发送表 `summary` 与 `entries`，见 [`stream_error_log_arrow`]。
+/// 两种格式共用缓存，最后都发送一条空消息表示结束。
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn get_error_log(
     job_id: i64,
+    transport: Option<Transport>,
     channel: tauri::ipc::Channel<Vec<u8>>,
-    cache: State<'_, RwLock<Cache>>,
-    pool: State<'_, RwLock<PgPool>>,
     app: AppHandle,
 ) -> Result<()> {
-    // 先查询缓存
-    if let Some(ceched_rmp) = cache.read().await.get(job_id) {
-        channel.send(ceched_rmp)?;
+    match transport.unwrap_or_default() {
+        Transport::MsgPack => stream_error_log(&app, job_id, &channel).await,
+        Transport::Arrow => stream_error_log_arrow(&app, job_id, &channel).await,
+    }
+}
+
+/// 边查询边按加载步标记异常迭代，逐块读取误差日志
+///
+/// 每块至少包含 `chunk_rows` 次迭代（最后一块除外），且只包含已结束的加载步，
+/// 因此单块的大小不超过 `chunk_rows` 与最长加载步的迭代次数之和。
+#[cfg(feature = "desktop")]
+pub(crate) struct EntryChunks<'a> {
+    rows: futures_util::stream::BoxStream<'a, sqlx::Result<ErrorLogEntry>>,
+    chunk: Vec<ErrorLogEntry>,
+    /// 当前加载步在 `chunk` 中的起点
+    step: usize,
+    chunk_rows: usize,
+}
+
+#[cfg(feature = "desktop")]
+impl<'a> EntryChunks<'a> {
+    pub(crate) fn new(pool: &'a PgPool, job_id: i64, chunk_rows: usize) -> Self {
+        Self {
+            rows: sqlx::query_as::<_, ErrorLogEntry>(ERROR_LOG_ENTRY_STMT)
+                .bind(job_id)
+                .fetch(pool),
+            chunk: Vec::new(),
+            step: 0,
+            chunk_rows,
+        }
+    }
+
+    /// 读取下一块，没有更多迭代时返回 `None`
+    pub(crate) async fn next(&mut self) -> Result<Option<Vec<ErrorLogEntry>>> {
+        while let Some(entry) = self.rows.try_next().await? {
+            let finished = self.chunk.last().is_some_and(|last| !last.same_step(&entry));
+            if finished {
+                ErrorLogEntry::mark_outliers(&mut self.chunk[self.step..]);
+                self.step = self.chunk.len();
+                if self.step >= self.chunk_rows {
+                    let chunk = std::mem::replace(&mut self.chunk, vec![entry]);
+                    self.step = 0;
+                    return Ok(Some(chunk));
+                }
+            }
+            self.chunk.push(entry);
+        }
+        ErrorLogEntry::mark_outliers(&mut self.chunk[self.step..]);
+        self.step = 0;
+        let chunk = std::mem::take(&mut self.chunk);
+        Ok((!chunk.is_empty()).then_some(chunk))
+    }
+}
+
+/// 边查询边发送误差日志，最后发送一条空消息表示结束，消息见 [`error_log_messages`]
+#[cfg(feature = "desktop")]
+async fn stream_error_log(
+    app: &AppHandle,
+    job_id: i64,
+    channel: &tauri::ipc::Channel<Vec<u8>>,
+) -> Result<()> {
+    error_log_messages(app, job_id, |message| ready(channel.send(message).map_err(Error::Tauri)))
+        .await?;
+    channel.send(Vec::new()).map_err(Error::Tauri)
+}
+
+/// [`stream_error_log`] 的列式版本，每条缓存的消息转换为至多两条 Arrow 消息
+///
+/// 表 `summary` 只在开头发送一次，`entries` 的每个 record batch 对应一条缓存的消息，
+/// 列与 [`ErrorLogSummary::record_batch`]、[`ErrorLogEntry::record_batch`] 相同，没有数据的表不发送。
+#[cfg(feature = "desktop")]
+async fn stream_error_log_arrow(
+    app: &AppHandle,
+    job_id: i64,
+    channel: &tauri::ipc::Channel<Vec<u8>>,
+) -> Result<()> {
+    let send = |message: Vec<u8>| -> Result<()> {
+        let (summary, entries) = decode_error_log_message(&message)?;
+        if !summary.is_empty() {
+            let batch = ErrorLogSummary::record_batch(summary.iter().map(|s| (job_id, s)))?;
+            channel.send(ipc_message(&batch, "summary")?)?;
+        }
+        if !entries.is_empty() {
+            let batch = ErrorLogEntry::record_batch(entries.iter().map(|e| (job_id, e)))?;
+            channel.send(ipc_message(&batch, "entries")?)?;
+        }
+        Ok(())
+    };
+    error_log_messages(app, job_id, |message| ready(send(message))).await?;
+    channel.send(Vec::new()).map_err(Error::Tauri)
+}
+
+/// 逐条产生误差日志消息，每凑满 [`MSGPACK_CHUNK_ROWS`] 次迭代一条
+///
+/// 每条消息为 MessagePack 编码的 `[summary, entries]`，完整的 `summary` 只在第一条消息中，
+/// 没有迭代时也产生一条。异常迭代按加载步标记，因此只包含已结束的加载步，单条消息可能超过块大小。
+/// 未命中缓存时边查询边压缩写入缓存，命中缓存时按原来的分块逐条产生，两种情况下内存占用都不随日志增长。
+/// 桌面应用与 HTTP 接口共用同一缓存。
+#[cfg(feature = "desktop")]
+pub(crate) async fn error_log_messages<F, Fut>(app: &AppHandle, job_id: i64, mut send: F) -> Result<()>
+where
+    F: FnMut(Vec<u8>) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    // 先于连接池读取，期间切换数据库时丢弃结果而不是把旧数据库的日志写入缓存
+    let (cached, generation) = {
+        let cache = app.state::<RwLock<Cache>>();
+        let cache = cache.read().await;
+        (cache.get(job_id), cache.generation())
+    };
+    if let Some(messages) = cached {
+        for message in messages {
+            send(message?).await?;
+        }
         return Ok(());
     }
 
-    // 没有缓存，再查询数据库
-    let stmt_entries = r#"
+    let pool = app.state::<RwLock<PgPool>>().read().await.clone();
+    let summary = sqlx::query_as::<_, ErrorLogSummary>(ERROR_LOG_SUMMARY_STMT)
+        .bind(job_id)
+        .fetch_all(&pool)
+        .await?;
+    let mut chunks = EntryChunks::new(&pool, job_id, MSGPACK_CHUNK_ROWS);
+
+    let mut cached = MessageWriter::new();
+    let entries = chunks.next().await?.unwrap_or_default();
+    let mut message = rmp_serde::to_vec(&(&summary, entries))?;
+    loop {
+        cached.write(&message)?;
+        send(message).await?;
+        match chunks.next().await? {
+            Some(entries) => message = rmp_serde::to_vec(&(&[] as &[ErrorLogSummary], entries))?,
+            None => break,
+        }
+    }
+
+    cache_in_background(app, generation, job_id, cached.finish()?);
+    Ok(())
+}
+
+/// 解码 [`error_log_messages`] 产生的一条消息
+#[cfg(feature = "desktop")]
+pub(crate) fn decode_error_log_message(
+    message: &[u8],
+) -> Result<(Vec<ErrorLogSummary>, Vec<ErrorLogEntry>)> {
+    Ok(rmp_serde::from_slice(message)?)
+}
+
+/// 在后台写入缓存，不等待正在读取缓存的命令
+#[cfg(feature = "desktop")]
+fn cache_in_background(app: &AppHandle, generation: u64, job_id: i64, compressed: Vec<u8>) {
+    let app = app.clone();
+    tokio::spawn(async move {
+        let cache = app.state::<RwLock<Cache>>();
+        cache.write().await.set_if_current(generation, job_id, compressed);
+    });
+}
+
+/// 查询 [`ErrorLogEntry`]，参数 `$1` 为任务 id
+const ERROR_LOG_ENTRY_STMT: &str = r#"
             SELECT 
-                (ROW_NUMBER() OVER (ORDER BY timestamp))::INTEGER as iters, load, error_u, error_phi 
+                (ROW_NUMBER() OVER (ORDER BY timestamp, iter))::INTEGER as iters, iter, load, error_u, error_phi, 
+                extract(EPOCH from timestamp - lag(timestamp) OVER (ORDER BY timestamp, iter))::DOUBLE PRECISION as elapsed 
             FROM error_log 
             WHERE job_id = $1 
-            ORDER BY timestamp;"#;
-    let stmt_summary = r#"
+            ORDER BY timestamp, iter;"#;
+
+/// 查询 [`ErrorLogSummary`]，参数 `$1` 为任务 id
+const ERROR_LOG_SUMMARY_STMT: &str = r#"
             SELECT 
                 load, iters, extract(EPOCH from lag(timestamp, -1) over (order by load) - timestamp)::DOUBLE PRECISION as cost 
             FROM error_log_summary 
             WHERE job_id = $1;"#;
 
-    let pool = pool.read().await;
-    let ret = tokio::try_join!(
-        sqlx::query_as::<_, ErrorLogSummary>(stmt_summary)
-            .bind(job_id)
-            .fetch_all(pool.deref()),
-        sqlx::query_as::<_, ErrorLogEntry>(stmt_entries)
-            .bind(job_id)
-            .fetch_all(pool.deref()),
-    );
-    match ret {
-        Ok(data) => {
-            let rmp = rmp_serde::to_vec(&data)?;
-            let cloned = rmp.clone();
-            tokio::spawn(async move {
-                let cache = app.state::<RwLock<Cache>>();
-                cache.write().await.set(job_id, &cloned).unwrap();
-            });
-
-            channel.send(rmp).map_err(Error::Tauri)
-        }
-        Err(err) => return Err(err.into()),
-    }
+/// 只查询任务的加载步汇总
+pub async fn fetch_error_log_summary(pool: &PgPool, job_id: i64) -> Result<Vec<ErrorLogSummary>> {
+    let summary = sqlx::query_as::<_, ErrorLogSummary>(ERROR_LOG_SUMMARY_STMT)
+        .bind(job_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(summary)
+}
+
+/// 查询任务的加载步汇总与误差日志，并标记耗时异常的迭代
+pub async fn fetch_error_log(
+    pool: &PgPool,
+    job_id: i64,
+) -> Result<(Vec<ErrorLogSummary>, Vec<ErrorLogEntry>)> {
+    let (summary, mut entries) = tokio::try_join!(
+        fetch_error_log_summary(pool, job_id),
+        async {
+            sqlx::query_as::<_, ErrorLogEntry>(ERROR_LOG_ENTRY_STMT)
+                .bind(job_id)
+                .fetch_all(pool)
+                .await
+                .map_err(Error::from)
+        },
+    )?;
+    ErrorLogEntry::mark_outliers(&mut entries);
+    Ok((summary, entries))
 }
 
 /// Get total solving time in seconds
+///
+/// Returns `0` for a job without any log entries. See [`get_timing_report`](super::get_timing_report)
+/// for a detailed breakdown.
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn get_total_time(job_id: i64, pool: State<'_, RwLock<PgPool>>) -> Result<f64> {
+    let pool = pool.read().await;
+    fetch_total_time(pool.deref(), job_id).await
+}
+
+#[cfg(feature = "desktop")]
+pub(crate) async fn fetch_total_time(pool: &PgPool, job_id: i64) -> Result<f64> {
     let stmt = r#"
         SELECT 
-            extract(EPOCH from max(timestamp) - min(timestamp))::DOUBLE PRECISION as total 
+            coalesce(extract(EPOCH from max(timestamp) - min(timestamp)), 0)::DOUBLE PRECISION as total 
         FROM error_log 
         WHERE job_id = $1;"#;
 
-    let pool = pool.read().await;
-
-    match sqlx::query(stmt).bind(job_id).fetch_one(pool.deref()).await {
+    match sqlx::query(stmt).bind(job_id).fetch_one(pool).await {
         Ok(row) => Ok(row.get(0)),
         Err(e) => Err(e.into()),
     }
 }
 
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn clear_error_log_cache(cache: State<'_, RwLock<Cache>>) -> Result<()> {
     cache.write().await.clear();
@@ -326,6 +953,190 @@ pub async fn clear_error_log_cache(cache: State<'_, RwLock<Cache>>) -> Result<()
 mod tests {
     use super::*;
 
+    fn entry(load: f64, iter: i32, elapsed: Option<f64>) -> ErrorLogEntry {
+        ErrorLogEntry {
+            iters: 0,
+            iter,
+            load,
+            error_u: 0.0,
+            error_phi: 0.0,
+            elapsed,
+            outlier: false,
+        }
+    }
+
+    #[test]
+    fn test_mark_outliers() {
+        let mut entries = vec![
+            entry(0.1, 1, None),
+            entry(0.1, 2, Some(2.0)),
+            entry(0.1, 3, Some(2.0)),
+            entry(0.1, 4, Some(30.0)),
+            // 新加载步的中位数单独计算
+            entry(0.2, 1, Some(20.0)),
+            entry(0.2, 2, Some(20.0)),
+            entry(0.2, 3, Some(30.0)),
+            // 中位数接近 0 时不误报
+            entry(0.3, 1, Some(0.001)),
+            entry(0.3, 2, Some(0.5)),
+            entry(0.3, 3, Some(0.001)),
+        ];
+        ErrorLogEntry::mark_outliers(&mut entries);
+
+        let outliers = entries.iter().map(|entry| entry.outlier).collect::<Vec<_>>();
+        assert_eq!(
+            outliers,
+            [false, false, false, true, false, false, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn test_mark_outliers_restarted_step() {
+        // 同一载荷重试，迭代次数重新计数，重试是单独的加载步
+        let mut entries = vec![
+            entry(0.1, 1, None),
+            entry(0.1, 2, Some(0.1)),
+            entry(0.1, 3, Some(0.1)),
+            entry(0.1, 4, Some(0.1)),
+            entry(0.1, 5, Some(0.1)),
+            entry(0.1, 6, Some(0.1)),
+            // 与上面合并时中位数为 0.1，重试的迭代会被误报
+            entry(0.1, 1, Some(2.0)),
+            entry(0.1, 2, Some(2.0)),
+            entry(0.1, 3, Some(2.0)),
+        ];
+        ErrorLogEntry::mark_outliers(&mut entries);
+
+        assert!(entries.iter().all(|entry| !entry.outlier));
+    }
+
+    #[test]
+    fn test_parse_columns() {
+        let logs = "JobInfo(id='666666', name='test_job', queue='default', n=4, nodes=['node1', 'node2'])\n\
+            {\"mesh\": 1}\n\
+            2023-01-01 10:00:00.000 INFO l=0.5 iter=1 err={ u=0.1 phi=0.2 }\n\
+            2023-01-01 10:01:00.500 INFO l=0.6 iter=2 err={ u=0.3 phi=0.4 }\n";
+        let ParsedLog { header, columns } = LogParser::parse(logs).unwrap();
+
+        assert_eq!(header.id.scheduler_id, "666666");
+        assert_eq!(header.nodes, ["node1", "node2"]);
+        assert_eq!(header.parameters.as_deref(), Some("{\"mesh\": 1}"));
+        assert_eq!(header.metadata["queue"], "default");
+        assert_eq!(columns.load, [0.5, 0.6]);
+        assert_eq!(columns.iter, [1, 2]);
+        assert_eq!(columns.error_u, [0.1, 0.3]);
+        assert_eq!(columns.error_phi, [0.2, 0.4]);
+        assert_eq!(
+            columns.timestamp[1] - columns.timestamp[0],
+            chrono::TimeDelta::milliseconds(60_500)
+        );
+    }
+
+    #[test]
+    fn test_entries_to_csv_indices() {
+        // 字段索引取自第一条误差日志且相对于行首，不受之前非日志行长度的影响
+        let logs = "solver started on 4 cpus\n\
+            2023-01-01 10:00:00.000 INFO l=0.5 iter=1 err={ u=0.1 phi=0.2 }\n\
+            2023-01-01 10:00:01.000 INFO l=0.7 iter=2 err={ u=0.3 phi=0.4 }\n";
+        assert_eq!(
+            LogParser::entries_to_csv(logs, 42).unwrap(),
+            "2023-01-01 10:00:00.000,0.5,1,0.1,0.2,42\n\
+            2023-01-01 10:00:01.000,0.7,2,0.3,0.4,42\n"
+        );
+    }
+
+    #[test]
+    fn test_header_fields() {
+        let line = "12:00 JobInfo(id='123[4].pbs01', name=\"it's\", queue=long, n=4, nodes=['node1', 'node2'], \
+            walltime='01:00:00', exit_status=0, load=0.5, rerun=False, submitted=None, \
+            resources={'mem': '4gb', 'ncpus': 4}, nested=[[1, 2], []], note='a\\'b')";
+        let fields = HeaderFields::parse(line).unwrap();
+        assert_eq!(
+            Value::Object(fields),
+            serde_json::json!({
+                "id": "123[4].pbs01",
+                "name": "it's",
+                "queue": "long",
+                "n": 4,
+                "nodes": ["node1", "node2"],
+                "walltime": "01:00:00",
+                "exit_status": 0,
+                "load": 0.5,
+                "rerun": false,
+                "submitted": null,
+                "resources": "{'mem': '4gb', 'ncpus': 4}",
+                "nested": [[1, 2], []],
+                "note": "a'b",
+            })
+        );
+
+        assert_eq!(HeaderFields::parse("JobInfo()").unwrap().len(), 0);
+        for line in [
+            "JobInfo(",
+            "JobInfo(id)",
+            "JobInfo(=1)",
+            "JobInfo(id=)",
+            "JobInfo(id='1)",
+            "JobInfo(nodes=[1, 2)",
+            "JobInfo(a={)",
+            "JobInfo(a=1 b=2]",
+        ] {
+            assert!(HeaderFields::parse(line).is_err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        const HEADER: &str = "JobInfo(id='1', name='a', queue='q', n=4, nodes=['node1'])";
+        const ENTRY: &str = "2023-01-01 10:00:00.000 INFO l=0.5 iter=1 err={ u=0.1 phi=0.2 }";
+        // (日志, 出错的行号)，`None` 表示与具体行无关
+        let corpus = [
+            (String::new(), None),
+            ("\n\n".to_owned(), None),
+            (HEADER.to_owned(), Some(2)),
+            (format!("{HEADER}\n{{}}"), Some(3)),
+            (format!("garbage\n{{}}\n{ENTRY}\n"), Some(1)),
+            (format!("JobInfo(id='1', name='a')\n{{}}\n{ENTRY}\n"), Some(1)),
+            (format!("{}\n{{}}\n{ENTRY}\n", HEADER.replace("'1'", "''")), Some(1)),
+            (format!("{}\n{{}}\n{ENTRY}\n", HEADER.replace("n=4", "n=4294967296")), Some(1)),
+            (format!("{HEADER}\n{{}}\nno entries\n"), None),
+            (format!("{HEADER}\n{{}}\n{}\n", ENTRY.replace("01-01", "13-01")), None),
+        ];
+        for (logs, line) in corpus {
+            match LogParser::parse(&logs) {
+                Err(Error::LogLine { line: actual, .. }) => assert_eq!(Some(actual), line, "{logs}"),
+                Err(Error::LogFormat(_)) => assert_eq!(line, None, "{logs}"),
+                result => panic!("unexpected {result:?} for {logs}"),
+            }
+        }
+
+        // 后续行布局与第一行不同时逐行匹配，不越界也不截错字段
+        let logs = format!(
+            "{HEADER}\n{{}}\n{ENTRY}\n\
+            2023-01-01 10:00:01.000 l=0.5 iter=2 err={{ u=0.3 phi=0.4 }}\n\
+            2023-01-01 10:00:02.000 步 l=0.5 iter=3 err={{ u=0.5 phi=0.6 }}\n\
+            2023-01-01 10:00:03.000 INFO l=0.5 iter=10 err={{ u=0.7 phi=0.8 }}\n"
+        );
+        let ParsedLog { columns, .. } = LogParser::parse(&logs).unwrap();
+        assert_eq!(columns.iter, [1, 2, 3, 10]);
+        assert_eq!(columns.error_u, [0.1, 0.3, 0.5, 0.7]);
+
+        // 无法识别的作业号不影响导入
+        let logs = format!("{}\n{{}}\n{ENTRY}\n", HEADER.replace("'1'", "'abc'"));
+        let ParsedLog { header, .. } = LogParser::parse(&logs).unwrap();
+        assert_eq!(header.id.scheduler_id, "abc");
+    }
+
+    fn compress(message: &[u8]) -> Vec<u8> {
+        let mut writer = MessageWriter::new();
+        writer.write(message).unwrap();
+        writer.finish().unwrap()
+    }
+
+    fn messages(cache: &Cache, key: i64) -> Vec<Vec<u8>> {
+        cache.get(key).unwrap().collect::<Result<_>>().unwrap()
+    }
+
     #[test]
     fn test_cache_new() {
         let cache = Cache::new();
@@ -341,15 +1152,13 @@ mod tests {
         let value = b"test data";
 
         // 设置缓存项
-        assert!(cache.set(key, value).is_ok());
+        cache.set(key, compress(value));
 
         // 验证缓存项存在
         assert!(cache.has(key));
 
         // 获取缓存项
-        let retrieved = cache.get(key);
-        assert!(retrieved.is_some());
-        assert_eq!(retrieved.unwrap(), value);
+        assert_eq!(messages(&cache, key), [value]);
     }
 
     #[test]
@@ -372,14 +1181,13 @@ mod tests {
         let value2 = b"second data";
 
         // 设置第一个值
-        assert!(cache.set(key, value1).is_ok());
+        cache.set(key, compress(value1));
 
         // 尝试设置相同的键（应该被忽略）
-        assert!(cache.set(key, value2).is_ok());
+        cache.set(key, compress(value2));
 
         // 验证值仍然是第一个
-        let retrieved = cache.get(key);
-        assert_eq!(retrieved.unwrap(), value1);
+        assert_eq!(messages(&cache, key), [value1]);
     }
 
     #[test]
@@ -389,7 +1197,7 @@ mod tests {
         // 填满缓存（最大容量为8）
         for i in 0..8 {
             let value = format!("data{}", i).into_bytes();
-            assert!(cache.set(i, &value).is_ok());
+            cache.set(i, compress(&value));
         }
 
         // 验证所有项都存在
@@ -399,7 +1207,7 @@ mod tests {
 
         // 添加第9个项，应该触发LRU驱逐（键0被移除）
         let value = b"data8";
-        assert!(cache.set(8, value).is_ok());
+        cache.set(8, compress(value));
 
         // 验证键0已被移除，其他项仍然存在
         assert!(!cache.has(0));
@@ -415,7 +1223,7 @@ mod tests {
         let value = b"test data";
 
         // 设置并确认存在
-        assert!(cache.set(key, value).is_ok());
+        cache.set(key, compress(value));
         assert!(cache.has(key));
 
         // 删除项
@@ -433,7 +1241,7 @@ mod tests {
         // 添加几个项
         for i in 0..3 {
             let value = format!("data{}", i).into_bytes();
-            assert!(cache.set(i, &value).is_ok());
+            cache.set(i, compress(&value));
         }
 
         // 验证项存在
@@ -448,20 +1256,49 @@ mod tests {
         assert_eq!(cache.queue.len(), 0);
     }
 
+    #[test]
+    fn test_cache_generation() {
+        let mut cache = Cache::new();
+        let generation = cache.generation();
+
+        // 清空之前开始的查询不再写入
+        cache.clear();
+        cache.set_if_current(generation, 1, compress(b"old database"));
+        assert!(!cache.has(1));
+
+        cache.set_if_current(cache.generation(), 1, compress(b"new database"));
+        assert_eq!(messages(&cache, 1), [b"new database"]);
+    }
+
     #[test]
     fn test_cache_compression() {
         let mut cache = Cache::new();
         let key = 1i64;
 
-        // 使用较大的数据测试压缩/解压缩
+        // 使用较大的数据测试压缩/解压缩，空消息也应原样读出
         let large_data: Vec<u8> = (0..1000).map(|i| (i % 256) as u8).collect();
+        let mut writer = MessageWriter::new();
+        for message in [&large_data[..], &[], b"end"] {
+            writer.write(message).unwrap();
+        }
 
         // 设置缓存
-        assert!(cache.set(key, &large_data).is_ok());
+        cache.set(key, writer.finish().unwrap());
+
+        // 获取并验证数据完整性与顺序
+        assert_eq!(messages(&cache, key), [&large_data[..], &[], b"end"]);
+    }
+
+    #[cfg(feature = "desktop")]
+    #[test]
+    fn test_decode_error_log_message() {
+        let summary = [ErrorLogSummary { load: 0.5, iters: 3, cost: None }];
+        let message = rmp_serde::to_vec(&(&summary, [entry(0.5, 7, Some(1.5))])).unwrap();
 
-        // 获取并验证数据完整性
-        let retrieved = cache.get(key);
-        assert!(retrieved.is_some());
-        assert_eq!(retrieved.unwrap(), large_data);
+        let (summary, entries) = decode_error_log_message(&message).unwrap();
+        assert_eq!((summary[0].load, summary[0].iters, summary[0].cost), (0.5, 3, None));
+        assert_eq!((entries[0].load, entries[0].elapsed), (0.5, Some(1.5)));
+        // 消息中不含迭代计数，解码后的消息不再划分加载步
+        assert_eq!(entries[0].iter, 0);
     }
 }
@@ -0,0 +1,301 @@
+use super::{fetch_error_log, fetch_job, ErrorLogEntry, ErrorLogSummary, JobInfo, Result};
+use crate::error::Error;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+use rust_xlsxwriter::Workbook;
+use sqlx::PgPool;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "desktop")]
+use {std::ops::Deref, tauri::State, tokio::sync::RwLock};
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+    Xlsx,
+}
+
+impl ExportFormat {
+    /// 根据文件扩展名推断导出格式
+    pub(crate) fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(Self::Csv),
+            "parquet" => Some(Self::Parquet),
+            "xlsx" => Some(Self::Xlsx),
+            _ => None,
+        }
+    }
+
+    /// 优先使用指定的格式，否则根据文件扩展名推断
+    pub(crate) fn resolve(format: Option<Self>, path: &Path) -> Result<Self> {
+        format
+            .or_else(|| Self::from_path(path))
+            .ok_or_else(|| Error::ExportFormat(path.display().to_string()))
+    }
+
+    pub(crate) fn write(self, jobs: &[JobExport], path: &Path) -> Result<()> {
+        match self {
+            Self::Csv => write_csv(jobs, path),
+            Self::Parquet => write_parquet(jobs, path),
+            Self::Xlsx => write_xlsx(jobs, path),
+        }
+    }
+}
+
+/// 待导出的单个任务
+pub(crate) struct JobExport {
+    pub(crate) info: JobInfo,
+    pub(crate) summary: Vec<ErrorLogSummary>,
+    pub(crate) entries: Vec<ErrorLogEntry>,
+}
+
+impl JobExport {
+    pub(crate) async fn fetch(pool: &PgPool, job_id: i64) -> Result<Self> {
+        let info = fetch_job(pool, job_id).await?;
+        let (summary, entries) = fetch_error_log(pool, job_id).await?;
+        Ok(Self {
+            info,
+            summary,
+            entries,
+        })
+    }
+}
+
+/// 作业信息表的列，与 Parquet 元数据中的 `insight.jobs` 包含相同的任务字段
+const JOB_HEADER: [&str; 11] = [
+    "id", "name", "queue", "num_cpu", "nodes", "parameters",
+    "scheduler", "scheduler_id", "array_job_id", "array_index", "metadata",
+];
+const ENTRY_HEADER: [&str; 7] = [
+    "job_id", "iters", "load", "error_u", "error_phi", "elapsed", "outlier",
+];
+const SUMMARY_HEADER: [&str; 4] = ["job_id", "load", "iters", "cost"];
+
+/// 在文件名后追加后缀，例如 `out.csv` -> `out_summary.csv`
+pub(crate) fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{stem}_{suffix}");
+    if let Some(ext) = path.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
+
+/// 作业信息中的参数以 JSON 字符串导出
+fn parameters_string(info: &JobInfo) -> String {
+    info.parameters
+        .as_ref()
+        .map(|parameters| parameters.to_string())
+        .unwrap_or_default()
+}
+
+/// 导出为 CSV
+///
+/// 误差日志写入 `path`，加载步汇总写入 `<name>_summary.csv`，作业信息写入 `<name>_jobs.csv`
+fn write_csv(jobs: &[JobExport], path: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(ENTRY_HEADER)?;
+    for job in jobs {
+        for entry in &job.entries {
+            writer.serialize((
+                job.info.id,
+                entry.iters,
+                entry.load,
+                entry.error_u,
+                entry.error_phi,
+                entry.elapsed,
+                entry.outlier,
+            ))?;
+        }
+    }
+    writer.flush()?;
+
+    let mut writer = csv::Writer::from_path(sibling_path(path, "summary"))?;
+    writer.write_record(SUMMARY_HEADER)?;
+    for job in jobs {
+        for summary in &job.summary {
+            writer.serialize((job.info.id, summary.load, summary.iters, summary.cost))?;
+        }
+    }
+    writer.flush()?;
+
+    let mut writer = csv::Writer::from_path(sibling_path(path, "jobs"))?;
+    writer.write_record(JOB_HEADER)?;
+    for JobExport { info, .. } in jobs {
+        writer.serialize((
+            info.id,
+            &info.name,
+            &info.queue,
+            info.num_cpu,
+            info.nodes.join(","),
+            parameters_string(info),
+            &info.scheduler,
+            &info.scheduler_id,
+            &info.array_job_id,
+            info.array_index,
+            info.metadata.to_string(),
+        ))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 导出为 Apache Parquet
+///
+/// 误差日志写入 `path`，加载步汇总写入 `<name>_summary.parquet`，
+/// 作业信息以 JSON 形式保存在两个文件的 `insight.jobs` 键值元数据中
+fn write_parquet(jobs: &[JobExport], path: &Path) -> Result<()> {
+    let infos = jobs.iter().map(|job| &job.info).collect::<Vec<_>>();
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_key_value_metadata(Some(vec![KeyValue::new(
+            String::from("insight.jobs"),
+            serde_json::to_string(&infos)?,
+        )]))
+        .build();
+
+    let entries = jobs
+        .iter()
+        .flat_map(|job| job.entries.iter().map(|entry| (job.info.id, entry)));
+    let batch = ErrorLogEntry::record_batch(entries)?;
+    let file = File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties.clone()))?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    let summaries = jobs
+        .iter()
+        .flat_map(|job| job.summary.iter().map(|summary| (job.info.id, summary)));
+    let batch = ErrorLogSummary::record_batch(summaries)?;
+    let file = File::create(sibling_path(path, "summary"))?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// 导出为 Excel
+///
+/// 工作表依次为作业信息 `jobs`、加载步汇总 `summary` 与误差日志 `error_log`。
+/// 误差日志超出单个工作表的行数上限时拆分为 `error_log_2`、`error_log_3` 等。
+fn write_xlsx(jobs: &[JobExport], path: &Path) -> Result<()> {
+    /// 单个工作表的最大数据行数（不含表头）
+    const MAX_ROWS: usize = 1_048_575;
+
+    let mut workbook = Workbook::new();
+
+    let sheet = workbook.add_worksheet().set_name("jobs")?;
+    sheet.write_row(0, 0, JOB_HEADER)?;
+    for (row, JobExport { info, .. }) in (1..).zip(jobs) {
+        sheet.write(row, 0, info.id)?;
+        sheet.write(row, 1, info.name.as_str())?;
+        sheet.write(row, 2, info.queue.as_str())?;
+        sheet.write(row, 3, info.num_cpu)?;
+        sheet.write(row, 4, info.nodes.join(","))?;
+        sheet.write(row, 5, parameters_string(info))?;
+        sheet.write(row, 6, info.scheduler.as_str())?;
+        sheet.write(row, 7, info.scheduler_id.as_str())?;
+        sheet.write(row, 8, info.array_job_id.as_deref())?;
+        sheet.write(row, 9, info.array_index)?;
+        sheet.write(row, 10, info.metadata.to_string())?;
+    }
+
+    let sheet = workbook.add_worksheet().set_name("summary")?;
+    sheet.write_row(0, 0, SUMMARY_HEADER)?;
+    let summaries = jobs
+        .iter()
+        .flat_map(|job| job.summary.iter().map(|summary| (job.info.id, summary)));
+    for (row, (job_id, summary)) in (1..).zip(summaries) {
+        sheet.write(row, 0, job_id)?;
+        sheet.write(row, 1, summary.load)?;
+        sheet.write(row, 2, summary.iters)?;
+        sheet.write(row, 3, summary.cost)?;
+    }
+
+    let entries = jobs
+        .iter()
+        .flat_map(|job| job.entries.iter().map(|entry| (job.info.id, entry)))
+        .collect::<Vec<_>>();
+    for (idx, chunk) in entries.chunks(MAX_ROWS).enumerate() {
+        let name = match idx {
+            0 => String::from("error_log"),
+            n => format!("error_log_{}", n + 1),
+        };
+        let sheet = workbook.add_worksheet().set_name(name)?;
+        sheet.write_row(0, 0, ENTRY_HEADER)?;
+        for (row, (job_id, entry)) in (1..).zip(chunk) {
+            sheet.write(row, 0, *job_id)?;
+            sheet.write(row, 1, entry.iters)?;
+            sheet.write(row, 2, entry.load)?;
+            sheet.write(row, 3, entry.error_u)?;
+            sheet.write(row, 4, entry.error_phi)?;
+            sheet.write(row, 5, entry.elapsed)?;
+            sheet.write(row, 6, entry.outlier)?;
+        }
+    }
+
+    workbook.save(path)?;
+    Ok(())
+}
+
+/// 将一个或多个任务的误差日志、加载步汇总及作业信息导出到文件
+///
+/// `format` 为空时根据 `file` 的扩展名推断，支持 `csv`、`parquet` 与 `xlsx`。
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn export_job(
+    job_ids: Vec<i64>,
+    file: PathBuf,
+    format: Option<ExportFormat>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let format = ExportFormat::resolve(format, &file)?;
+
+    let pool = pool.read().await;
+    let mut jobs = Vec::with_capacity(job_ids.len());
+    for job_id in job_ids {
+        jobs.push(JobExport::fetch(pool.deref(), job_id).await?);
+    }
+    drop(pool);
+
+    tauri::async_runtime::spawn_blocking(move || format.write(&jobs, &file)).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_format_from_path() {
+        assert!(matches!(
+            ExportFormat::from_path(Path::new("a/b.CSV")),
+            Some(ExportFormat::Csv)
+        ));
+        assert!(matches!(
+            ExportFormat::from_path(Path::new("b.parquet")),
+            Some(ExportFormat::Parquet)
+        ));
+        assert!(matches!(
+            ExportFormat::from_path(Path::new("b.xlsx")),
+            Some(ExportFormat::Xlsx)
+        ));
+        assert!(ExportFormat::from_path(Path::new("b.txt")).is_none());
+        assert!(ExportFormat::from_path(Path::new("b")).is_none());
+    }
+
+    #[test]
+    fn test_sibling_path() {
+        assert_eq!(
+            sibling_path(Path::new("dir/out.csv"), "summary"),
+            Path::new("dir/out_summary.csv")
+        );
+        assert_eq!(sibling_path(Path::new("out"), "jobs"), Path::new("out_jobs"));
+    }
+}
@@ -0,0 +1,266 @@
+use super::Result;
+use crate::error::Error;
+use arrow::array::{
+    ArrayBuilder, ArrayRef, BooleanBuilder, Float32Builder, Float64Builder, Int16Builder,
+    Int32Builder, Int64Builder, ListBuilder, RecordBatch, StringBuilder,
+    TimestampMicrosecondBuilder,
+};
+use arrow::datatypes::{Field, Schema};
+use arrow::error::ArrowError;
+use arrow::ipc::writer::StreamWriter;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::postgres::{PgColumn, PgRow};
+use sqlx::{Column, Row, TypeInfo};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 每个 Arrow record batch 的最大行数
+pub(crate) const ARROW_BATCH_ROWS: usize = 65_536;
+
+/// 通过 `Channel` 发送数据时的编码
+#[derive(Debug, Default, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    /// 按行编码的 MessagePack
+    #[default]
+    MsgPack,
+    /// 按列编码的 Arrow IPC，每条消息见 [`ipc_message`]
+    Arrow,
+}
+
+/// 将一个 record batch 编码为完整的 Arrow IPC 流，作为一条 `Channel` 消息发送
+///
+/// 表名 `table` 保存在 schema 元数据的 `table` 键中，前端据此区分同一 `Channel` 上的不同表，
+/// 并可逐条用 `tableFromIPC` 解码。
+pub(crate) fn ipc_message(batch: &RecordBatch, table: &str) -> Result<Vec<u8>> {
+    let metadata = HashMap::from([(String::from("table"), table.to_owned())]);
+    let schema = Arc::new(batch.schema().as_ref().clone().with_metadata(metadata));
+    let mut stream = IpcStream::new(&schema)?;
+    stream.write(&batch.clone().with_schema(schema)?)?;
+    stream.finish()
+}
+
+/// 分块输出的单个 Arrow IPC 流
+///
+/// 创建时写入 schema，之后每写入一个 record batch 即可用 [`take`](Self::take) 取出编码好的字节发送，
+/// 客户端（如 `pyarrow.ipc.open_stream`）按顺序读到的是一个完整的流。
+pub(crate) struct IpcStream {
+    writer: StreamWriter<Vec<u8>>,
+}
+
+impl IpcStream {
+    pub(crate) fn new(schema: &Schema) -> Result<Self> {
+        Ok(Self {
+            writer: StreamWriter::try_new(Vec::new(), schema)?,
+        })
+    }
+
+    pub(crate) fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        Ok(self.writer.write(batch)?)
+    }
+
+    /// 取出已编码、尚未发送的字节
+    pub(crate) fn take(&mut self) -> Vec<u8> {
+        std::mem::take(self.writer.get_mut())
+    }
+
+    /// 写入流结束标记，返回剩余的字节
+    pub(crate) fn finish(mut self) -> Result<Vec<u8>> {
+        self.writer.finish()?;
+        Ok(self.take())
+    }
+}
+
+/// 按 Postgres 列类型逐行构建 record batch
+pub(crate) struct RowBatchBuilder {
+    names: Vec<String>,
+    columns: Vec<ColumnBuilder>,
+}
+
+impl RowBatchBuilder {
+    /// 根据查询结果的列信息创建，遇到不支持的列类型时返回错误
+    pub(crate) fn new(columns: &[PgColumn]) -> Result<Self> {
+        let (names, columns) = columns
+            .iter()
+            .map(|column| {
+                let type_name = column.type_info().name();
+                let builder = ColumnBuilder::new(type_name).ok_or_else(|| {
+                    Error::Arrow(ArrowError::NotYetImplemented(format!(
+                        "column '{}' of type {type_name}",
+                        column.name()
+                    )))
+                })?;
+                Ok((column.name().to_owned(), builder))
+            })
+            .collect::<Result<(Vec<_>, Vec<_>)>>()?;
+        Ok(Self { names, columns })
+    }
+
+    pub(crate) fn append(&mut self, row: &PgRow) -> Result<()> {
+        for (index, column) in self.columns.iter_mut().enumerate() {
+            column.append(row, index)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.columns.first().map_or(0, ColumnBuilder::len)
+    }
+
+    /// 取出已追加的行，构建器可继续使用
+    pub(crate) fn finish(&mut self) -> Result<RecordBatch> {
+        let arrays = self
+            .columns
+            .iter_mut()
+            .map(ColumnBuilder::finish)
+            .collect::<Vec<_>>();
+        let fields = self
+            .names
+            .iter()
+            .zip(&arrays)
+            .map(|(name, array)| Field::new(name, array.data_type().clone(), true))
+            .collect::<Vec<_>>();
+        Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?)
+    }
+}
+
+enum ColumnBuilder {
+    Boolean(BooleanBuilder),
+    Int16(Int16Builder),
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Float32(Float32Builder),
+    Float64(Float64Builder),
+    Utf8(StringBuilder),
+    /// JSON 以文本形式传输
+    Json(StringBuilder),
+    Utf8List(ListBuilder<StringBuilder>),
+    Timestamp(TimestampMicrosecondBuilder),
+    TimestampTz(TimestampMicrosecondBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(type_name: &str) -> Option<Self> {
+        Some(match type_name {
+            "BOOL" => Self::Boolean(BooleanBuilder::new()),
+            "INT2" => Self::Int16(Int16Builder::new()),
+            "INT4" => Self::Int32(Int32Builder::new()),
+            "INT8" => Self::Int64(Int64Builder::new()),
+            "FLOAT4" => Self::Float32(Float32Builder::new()),
+            "FLOAT8" => Self::Float64(Float64Builder::new()),
+            "TEXT" | "VARCHAR" | "NAME" => Self::Utf8(StringBuilder::new()),
+            "JSON" | "JSONB" => Self::Json(StringBuilder::new()),
+            "TEXT[]" | "VARCHAR[]" => Self::Utf8List(ListBuilder::new(StringBuilder::new())),
+            "TIMESTAMP" => Self::Timestamp(TimestampMicrosecondBuilder::new()),
+            "TIMESTAMPTZ" => {
+                Self::TimestampTz(TimestampMicrosecondBuilder::new().with_timezone("UTC"))
+            }
+            _ => return None,
+        })
+    }
+
+    fn append(&mut self, row: &PgRow, index: usize) -> Result<()> {
+        match self {
+            Self::Boolean(b) => b.append_option(row.try_get::<Option<bool>, _>(index)?),
+            Self::Int16(b) => b.append_option(row.try_get::<Option<i16>, _>(index)?),
+            Self::Int32(b) => b.append_option(row.try_get::<Option<i32>, _>(index)?),
+            Self::Int64(b) => b.append_option(row.try_get::<Option<i64>, _>(index)?),
+            Self::Float32(b) => b.append_option(row.try_get::<Option<f32>, _>(index)?),
+            Self::Float64(b) => b.append_option(row.try_get::<Option<f64>, _>(index)?),
+            Self::Utf8(b) => b.append_option(row.try_get::<Option<String>, _>(index)?),
+            Self::Json(b) => {
+                let value = row.try_get::<Option<serde_json::Value>, _>(index)?;
+                b.append_option(value.map(|value| value.to_string()))
+            }
+            Self::Utf8List(b) => match row.try_get::<Option<Vec<String>>, _>(index)? {
+                Some(values) => b.append_value(values.into_iter().map(Some)),
+                None => b.append_null(),
+            },
+            Self::Timestamp(b) => {
+                let value = row.try_get::<Option<NaiveDateTime>, _>(index)?;
+                b.append_option(value.map(|t| t.and_utc().timestamp_micros()))
+            }
+            Self::TimestampTz(b) => {
+                let value = row.try_get::<Option<DateTime<Utc>>, _>(index)?;
+                b.append_option(value.map(|t| t.timestamp_micros()))
+            }
+        }
+        Ok(())
+    }
+
+    fn builder(&self) -> &dyn ArrayBuilder {
+        match self {
+            Self::Boolean(b) => b,
+            Self::Int16(b) => b,
+            Self::Int32(b) => b,
+            Self::Int64(b) => b,
+            Self::Float32(b) => b,
+            Self::Float64(b) => b,
+            Self::Utf8(b) | Self::Json(b) => b,
+            Self::Utf8List(b) => b,
+            Self::Timestamp(b) | Self::TimestampTz(b) => b,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.builder().len()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Boolean(b) => Arc::new(b.finish()),
+            Self::Int16(b) => Arc::new(b.finish()),
+            Self::Int32(b) => Arc::new(b.finish()),
+            Self::Int64(b) => Arc::new(b.finish()),
+            Self::Float32(b) => Arc::new(b.finish()),
+            Self::Float64(b) => Arc::new(b.finish()),
+            Self::Utf8(b) | Self::Json(b) => Arc::new(b.finish()),
+            Self::Utf8List(b) => Arc::new(b.finish()),
+            Self::Timestamp(b) | Self::TimestampTz(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float64Array, Int64Array};
+    use arrow::ipc::reader::StreamReader;
+
+    #[test]
+    fn test_ipc_stream() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("job_id", arrow::datatypes::DataType::Int64, false),
+            Field::new("load", arrow::datatypes::DataType::Float64, true),
+        ]));
+        let batch = |ids: Vec<i64>, loads: Vec<Option<f64>>| {
+            let columns: Vec<ArrayRef> = vec![
+                Arc::new(Int64Array::from(ids)),
+                Arc::new(Float64Array::from(loads)),
+            ];
+            RecordBatch::try_new(schema.clone(), columns).unwrap()
+        };
+        let batches = [batch(vec![1, 1], vec![Some(0.5), None]), batch(vec![2], vec![Some(1.0)])];
+
+        // 分块取出的字节依次拼接即为完整的流
+        let mut stream = IpcStream::new(&schema).unwrap();
+        let mut bytes = stream.take();
+        assert!(!bytes.is_empty());
+        for batch in &batches {
+            stream.write(batch).unwrap();
+            bytes.extend(stream.take());
+        }
+        bytes.extend(stream.finish().unwrap());
+
+        let reader = StreamReader::try_new(bytes.as_slice(), None).unwrap();
+        let decoded = reader.collect::<std::result::Result<Vec<_>, _>>().unwrap();
+        assert_eq!(decoded, batches);
+
+        // 单条消息是带表名的完整流
+        let message = ipc_message(&batches[0], "entries").unwrap();
+        let reader = StreamReader::try_new(message.as_slice(), None).unwrap();
+        assert_eq!(reader.schema().metadata()["table"], "entries");
+        let decoded = reader.collect::<std::result::Result<Vec<_>, _>>().unwrap();
+        assert_eq!(decoded[0].columns(), batches[0].columns());
+    }
+}
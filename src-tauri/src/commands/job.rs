@@ -1,59 +1,774 @@
-use std::ops::Deref;
-
-use crate::commands::Cache;
 use crate::error::Error;
 
-use super::{query_as_and_send, Result};
+use super::Result;
+use chrono::NaiveDateTime;
+use lazy_static::lazy_static;
+use regex::Regex;
+use super::pg_arguments;
 use sqlx::postgres::PgArguments;
-use sqlx::PgPool;
-use tauri::State;
-use tokio::sync::RwLock;
+use sqlx::{Arguments, PgPool};
+#[cfg(feature = "desktop")]
+use {
+    super::{query_and_send_arrow, query_as_and_stream, BackgroundError, Transport},
+    crate::commands::Cache,
+    std::ops::Deref,
+    tauri::{AppHandle, Emitter, Manager, State},
+    tokio::sync::RwLock,
+};
 
+/// `id` 为数据库分配的代理键，调度系统的作业号见 `scheduler_id`
 #[derive(Debug, serde::Serialize, sqlx::FromRow)]
 pub struct JobInfo {
-    id: i64,
-    name: String,
-    queue: String,
-    num_cpu: i32,
-    parameters: Option<serde_json::Value>,
+    pub(crate) id: i64,
+    pub(crate) name: String,
+    pub(crate) queue: String,
+    pub(crate) num_cpu: i32,
+    pub(crate) parameters: Option<serde_json::Value>,
+    pub(crate) nodes: Vec<String>,
+    /// 调度系统，如 `pbs`、`slurm`，无法判断时为空
+    pub(crate) scheduler: String,
+    /// 日志中的原始作业号
+    pub(crate) scheduler_id: String,
+    /// 数组作业的父作业号
+    pub(crate) array_job_id: Option<String>,
+    /// 数组作业中的序号
+    pub(crate) array_index: Option<i32>,
+    /// 日志中作业信息行的全部字段，如 `user`、`walltime`、`exit_status`
+    pub(crate) metadata: serde_json::Value,
+    pub(crate) starred: bool,
+    /// 按字母顺序排列
+    pub(crate) tags: Vec<String>,
+    /// 导入时间，早于该字段加入的任务为空
+    pub(crate) imported_at: Option<NaiveDateTime>,
+    /// 以下为汇总指标，没有误差日志时为空
+    pub(crate) iterations: Option<i64>,
+    /// 收敛的加载步数
+    pub(crate) load_steps: Option<i32>,
+    pub(crate) failed_steps: Option<i32>,
+    pub(crate) final_load: Option<f64>,
+    /// 第一条与最后一条日志的间隔（秒）
+    pub(crate) wall_time: Option<f64>,
+    /// 移入回收站的时间，未删除时为空
+    pub(crate) deleted_at: Option<NaiveDateTime>,
+}
+
+/// 任务列表的筛选条件，均为空时返回全部任务
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct JobFilter {
+    /// 带有该标签
+    pub tag: Option<String>,
+    /// 备注中包含的文字，不区分大小写
+    pub note: Option<String>,
+    /// 仅返回收藏（`true`）或未收藏（`false`）的任务
+    pub starred: Option<bool>,
+    /// 搜索文字，匹配编号、作业号、名字、队列或参数值，不区分大小写
+    pub search: Option<String>,
+    /// 为 `true` 时只返回回收站中的任务，否则只返回未删除的任务
+    pub deleted: bool,
+    /// 为 `true` 时同时返回汇总指标，否则不连接 `job_summary`，指标均为空
+    pub metrics: bool,
+}
+
+impl JobFilter {
+    /// [`job_filter!`] 的参数 `$1` 至 `$6`
+    pub(crate) fn arguments(&self) -> Result<PgArguments> {
+        let search = self.search.as_deref().map(str::trim).filter(|search| !search.is_empty());
+        Ok(pg_arguments!(
+            &self.tag,
+            self.note.as_deref().map(like_pattern),
+            self.starred,
+            search,
+            search.map(like_pattern),
+            self.deleted,
+        ))
+    }
+}
+
+/// 转换为包含该文字的 `ILIKE` 模式，其中的通配符按原文匹配
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// [`JobInfo`] 的各列，须与 [`job_source!`] 一起使用
+///
+/// 带 `metrics` 时包含汇总指标，须与 `job_source!(metrics)` 一起使用，否则指标均为空。
+macro_rules! job_columns {
+    () => {
+        $crate::commands::job_columns!(@
+            "NULL::BIGINT AS iterations, NULL::INTEGER AS load_steps, NULL::INTEGER AS failed_steps,
+            NULL::DOUBLE PRECISION AS final_load, NULL::DOUBLE PRECISION AS wall_time")
+    };
+    (metrics) => {
+        $crate::commands::job_columns!(@
+            "s.iterations, s.load_steps, s.failed_steps, s.final_load, s.wall_time")
+    };
+    (@ $metrics:literal) => {
+        concat!(
+            r#"
+            j.id, j.name, j.queue, j.num_cpu, j.parameters, j.nodes,
+            j.scheduler, j.scheduler_id, j.array_job_id, j.array_index, j.metadata,
+            j.starred,
+            ARRAY(SELECT tag FROM job_tags t WHERE t.job_id = j.id ORDER BY tag) AS tags,
+            j.imported_at,
+            "#,
+            $metrics,
+            r#",
+            j.deleted_at"#
+        )
+    };
+}
+#[cfg_attr(not(feature = "desktop"), allow(unused_imports))]
+pub(crate) use job_columns;
+
+/// [`job_columns!`] 的 `FROM` 子句，`job_info` 的别名为 `j`，带 `metrics` 时汇总指标的别名为 `s`
+macro_rules! job_source {
+    () => {
+        r#"
+        FROM job_info j"#
+    };
+    (metrics) => {
+        r#"
+        FROM job_info j
+        LEFT JOIN job_summary s ON s.job_id = j.id"#
+    };
+}
+#[cfg_attr(not(feature = "desktop"), allow(unused_imports))]
+pub(crate) use job_source;
+
+/// 按 [`JobFilter`] 筛选的 `WHERE` 子句，`job_info` 的别名须为 `j`
+///
+/// 参数 `$1` 为标签，`$2` 为备注的 `ILIKE` 模式，`$3` 为是否收藏，`$4` 为搜索文字，
+/// `$5` 为其 `ILIKE` 模式，为 `NULL` 时不筛选。参数只在值为对象时匹配。
+/// `$6` 为是否只返回回收站中的任务。
+macro_rules! job_filter {
+    () => {
+        r#"
+        WHERE (j.deleted_at IS NOT NULL) = $6
+            AND ($1::TEXT IS NULL OR EXISTS (SELECT FROM job_tags t WHERE t.job_id = j.id AND t.tag = $1))
+            AND ($2::TEXT IS NULL OR EXISTS (SELECT FROM job_notes n WHERE n.job_id = j.id AND n.content ILIKE $2))
+            AND ($3::BOOLEAN IS NULL OR j.starred = $3)
+            AND ($4::TEXT IS NULL
+                OR j.id::TEXT = $4
+                OR j.scheduler_id ILIKE $5
+                OR j.name ILIKE $5
+                OR j.queue ILIKE $5
+                OR CASE WHEN jsonb_typeof(j.parameters) = 'object' THEN EXISTS (
+                    SELECT FROM jsonb_each_text(j.parameters) p WHERE p.value ILIKE $5
+                ) ELSE FALSE END)"#
+    };
+}
+
+const JOB_LIST_STMT: &str = concat!("SELECT", job_columns!(), job_source!(), job_filter!(), ";");
+
+const JOB_LIST_METRICS_STMT: &str =
+    concat!("SELECT", job_columns!(metrics), job_source!(metrics), job_filter!(), ";");
+
+/// 任务列表的查询语句，参数为 [`JobFilter::arguments`]
+pub(crate) fn job_list_stmt(filter: &JobFilter) -> &'static str {
+    if filter.metrics {
+        JOB_LIST_METRICS_STMT
+    } else {
+        JOB_LIST_STMT
+    }
 }
 
+const JOB_COUNT_STMT: &str = concat!("SELECT COUNT(*) FROM job_info j", job_filter!(), ";");
+
+/// 分页查询的排序列
+#[derive(Debug, Default, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum JobSortKey {
+    #[default]
+    Id,
+    SchedulerId,
+    Name,
+    Queue,
+    NumCpu,
+    ImportedAt,
+    Iterations,
+    FailedSteps,
+    FinalLoad,
+    WallTime,
+}
+
+impl JobSortKey {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::SchedulerId => "scheduler_id",
+            Self::Name => "name",
+            Self::Queue => "queue",
+            Self::NumCpu => "num_cpu",
+            Self::ImportedAt => "imported_at",
+            Self::Iterations => "iterations",
+            Self::FailedSteps => "failed_steps",
+            Self::FinalLoad => "final_load",
+            Self::WallTime => "wall_time",
+        }
+    }
+
+    /// 排序的列及其 SQL 类型，游标中的值按该类型比较
+    ///
+    /// 可以为空的列以最小值代替空值，使翻页时的比较与排序一致。
+    fn column(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Id => ("j.id", "BIGINT"),
+            Self::SchedulerId => ("j.scheduler_id", "TEXT"),
+            Self::Name => ("j.name", "TEXT"),
+            Self::Queue => ("j.queue", "TEXT"),
+            Self::NumCpu => ("j.num_cpu", "INTEGER"),
+            Self::ImportedAt => ("coalesce(j.imported_at, '-infinity')", "TIMESTAMP"),
+            Self::Iterations => ("coalesce(s.iterations, -1)", "BIGINT"),
+            Self::FailedSteps => ("coalesce(s.failed_steps, -1)", "INTEGER"),
+            Self::FinalLoad => ("coalesce(s.final_load, '-infinity')", "DOUBLE PRECISION"),
+            Self::WallTime => ("coalesce(s.wall_time, -1)", "DOUBLE PRECISION"),
+        }
+    }
+
+    fn value(self, job: &JobInfo) -> String {
+        match self {
+            Self::Id => job.id.to_string(),
+            Self::SchedulerId => job.scheduler_id.clone(),
+            Self::Name => job.name.clone(),
+            Self::Queue => job.queue.clone(),
+            Self::NumCpu => job.num_cpu.to_string(),
+            Self::ImportedAt => job
+                .imported_at
+                .map_or_else(|| "-infinity".into(), |at| at.format(TIMESTAMP_FORMAT).to_string()),
+            Self::Iterations => job.iterations.unwrap_or(-1).to_string(),
+            Self::FailedSteps => job.failed_steps.unwrap_or(-1).to_string(),
+            Self::FinalLoad => job.final_load.unwrap_or(f64::NEG_INFINITY).to_string(),
+            Self::WallTime => job.wall_time.unwrap_or(-1.0).to_string(),
+        }
+    }
+
+    /// 是否按汇总指标排序，此时须连接 `job_summary`
+    fn is_metric(self) -> bool {
+        matches!(self, Self::Iterations | Self::FailedSteps | Self::FinalLoad | Self::WallTime)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// 分页参数，按 `(sort, id)` 翻页，翻页期间新增或删除的任务不会导致重复或遗漏
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct JobPageRequest {
+    pub sort: JobSortKey,
+    pub order: SortOrder,
+    /// 上一页返回的 `next_cursor`，为空时从第一页开始，须与 `sort` 一致
+    pub cursor: Option<String>,
+    /// 每页的任务数，为空时返回全部
+    pub limit: Option<u32>,
+    /// 跳过的任务数，用于直接跳转到没有游标的页，跳过的任务越多越慢
+    pub offset: Option<u32>,
+}
+
+/// 一页任务
+#[derive(Debug, serde::Serialize)]
+pub struct JobPage {
+    /// 满足筛选条件的任务总数
+    pub total: i64,
+    pub jobs: Vec<JobInfo>,
+    /// 下一页的游标，已是最后一页时为空
+    pub next_cursor: Option<String>,
+}
+
+/// 游标中时间的格式，可由 PostgreSQL 直接解析
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+/// 游标的格式为 `排序列:编号:排序值`，对前端不透明
+fn encode_cursor(sort: JobSortKey, job: &JobInfo) -> String {
+    format!("{}:{}:{}", sort.name(), job.id, sort.value(job))
+}
+
+/// 解析游标，返回排序值与编号
+fn decode_cursor(cursor: &str, sort: JobSortKey) -> Result<(String, i64)> {
+    let invalid = |reason: &str| Error::Cursor(format!("{reason} in '{cursor}'"));
+    let (key, rest) = cursor.split_once(':').ok_or_else(|| invalid("missing sort key"))?;
+    if key != sort.name() {
+        return Err(invalid(&format!("sort key '{key}' does not match '{}'", sort.name())));
+    }
+    let (id, value) = rest.split_once(':').ok_or_else(|| invalid("missing id"))?;
+    let id = id.parse().map_err(|_| invalid("invalid id"))?;
+    // 超出列类型范围的值在数据库中会转换失败，此处一并拒绝
+    let valid = match sort.column().1 {
+        "BIGINT" => value.parse::<i64>().is_ok(),
+        "INTEGER" => value.parse::<i32>().is_ok(),
+        "DOUBLE PRECISION" => value.parse::<f64>().is_ok(),
+        "TIMESTAMP" => {
+            value == "-infinity" || NaiveDateTime::parse_from_str(value, TIMESTAMP_FORMAT).is_ok()
+        }
+        _ => true,
+    };
+    if !valid {
+        return Err(invalid("invalid sort value"));
+    }
+    Ok((value.to_owned(), id))
+}
+
+/// 分页查询语句，参数 `$7`、`$8` 为游标的排序值与编号，`$9` 为行数，`$10` 为跳过的行数
+///
+/// 按汇总指标排序时总是连接 `job_summary`，并一同返回指标。
+fn job_page_stmt(sort: JobSortKey, order: SortOrder, metrics: bool) -> String {
+    let (column, ty) = sort.column();
+    let (op, direction) = match order {
+        SortOrder::Asc => (">", "ASC"),
+        SortOrder::Desc => ("<", "DESC"),
+    };
+    let (columns, source) = if metrics || sort.is_metric() {
+        (job_columns!(metrics), job_source!(metrics))
+    } else {
+        (job_columns!(), job_source!())
+    };
+    format!(
+        concat!(
+            "SELECT{columns}{source}",
+            job_filter!(),
+            r#"
+            AND ($7::TEXT IS NULL OR ({column}, j.id) {op} ($7::{ty}, $8))
+        ORDER BY {column} {direction}, j.id {direction}
+        LIMIT $9 OFFSET $10;"#
+        ),
+        columns = columns,
+        source = source,
+        column = column,
+        op = op,
+        ty = ty,
+        direction = direction,
+    )
+}
+
+lazy_static! {
+    static ref SCHEDULER_ID_PATTERN: Regex =
+        Regex::new(r"^(\d+)(?:\[(\d*)\]|_\[(\d+)\]|_(\d+))?(?:\.([A-Za-z0-9][\w.-]*))?$").unwrap();
+}
+
+/// 调度系统分配的作业号
+///
+/// 支持的格式：
+///
+/// | 作业号 | 调度系统 | 说明 |
+/// | --- | --- | --- |
+/// | `12345` | 未知 | |
+/// | `12345.pbs01` | `pbs` | 带服务器名 |
+/// | `123[4]`、`123[4].pbs01` | `pbs` | 数组作业的子作业 |
+/// | `123[]` | `pbs` | 数组作业本身 |
+/// | `123_4`、`123_[4]` | `slurm` | 数组作业的子作业 |
+///
+/// 其它格式的作业号原样保存，调度系统为日志中注明的调度系统或空，不属于数组作业。
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SchedulerId {
+    pub(crate) scheduler: String,
+    pub(crate) scheduler_id: String,
+    pub(crate) array_job_id: Option<String>,
+    pub(crate) array_index: Option<i32>,
+}
+
+impl SchedulerId {
+    /// 解析作业号，`scheduler` 为日志中注明的调度系统，为空时由作业号格式推断
+    ///
+    /// 只有空白的作业号返回错误，无法识别的格式见 [`SchedulerId`]。
+    pub(crate) fn parse(id: &str, scheduler: Option<&str>) -> std::result::Result<Self, String> {
+        if id.trim().is_empty() {
+            return Err(String::from("empty job id"));
+        }
+        let scheduler = |inferred: &str| {
+            scheduler
+                .filter(|scheduler| !scheduler.is_empty())
+                .unwrap_or(inferred)
+                .to_lowercase()
+        };
+        let verbatim = || Self {
+            scheduler: scheduler(""),
+            scheduler_id: id.to_owned(),
+            array_job_id: None,
+            array_index: None,
+        };
+        let Some(cap) = SCHEDULER_ID_PATTERN.captures(id) else {
+            return Ok(verbatim());
+        };
+        let pbs_index = cap.get(2).map(|m| m.as_str());
+        let slurm_index = cap.get(3).or(cap.get(4)).map(|m| m.as_str());
+        let index = pbs_index.or(slurm_index).filter(|index| !index.is_empty());
+        // 序号超出范围时同样原样保存
+        let Ok(index) = index.map(str::parse).transpose() else {
+            return Ok(verbatim());
+        };
+        let inferred = if slurm_index.is_some() {
+            "slurm"
+        } else if pbs_index.is_some() || cap.get(5).is_some() {
+            "pbs"
+        } else {
+            ""
+        };
+
+        Ok(Self {
+            scheduler: scheduler(inferred),
+            scheduler_id: id.to_owned(),
+            array_job_id: index.is_some().then(|| cap[1].to_owned()),
+            array_index: index,
+        })
+    }
+}
+
+/// 发送任务列表，`filter` 为空时发送全部任务
+///
+/// 默认分块发送 MessagePack 编码的 [`JobInfo`]；使用 [`Transport::Arrow`] 时发送表 `jobs`，
+/// JSON 列以文本传输。
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn get_job_list(
+    filter: Option<JobFilter>,
+    transport: Option<Transport>,
     channel: tauri::ipc::Channel<Vec<u8>>,
     pool: State<'_, RwLock<PgPool>>,
 ) -> Result<()> {
-    let stmt = r#"
-        SELECT 
-            id, name, queue, num_cpu, parameters 
-        FROM job_info;"#;
-    let args = PgArguments::default();
-    query_as_and_send::<JobInfo>(stmt, args, pool, channel).await
+    let filter = filter.unwrap_or_default();
+    let (stmt, args) = (job_list_stmt(&filter), filter.arguments()?);
+    match transport.unwrap_or_default() {
+        Transport::MsgPack => query_as_and_stream::<JobInfo>(stmt, args, pool, channel).await,
+        Transport::Arrow => query_and_send_arrow(stmt, "jobs", args, pool, channel).await,
+    }
 }
 
+pub async fn fetch_job_list(pool: &PgPool, filter: &JobFilter) -> Result<Vec<JobInfo>> {
+    sqlx::query_as_with::<_, JobInfo, _>(job_list_stmt(filter), filter.arguments()?)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Sqlx)
+}
+
+/// 返回一页任务及满足筛选条件的任务总数
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn get_job_page(
+    filter: Option<JobFilter>,
+    page: Option<JobPageRequest>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<JobPage> {
+    let pool = pool.read().await;
+    fetch_job_page(pool.deref(), &filter.unwrap_or_default(), &page.unwrap_or_default()).await
+}
+
+pub async fn fetch_job_page(
+    pool: &PgPool,
+    filter: &JobFilter,
+    page: &JobPageRequest,
+) -> Result<JobPage> {
+    let cursor = page
+        .cursor
+        .as_deref()
+        .map(|cursor| decode_cursor(cursor, page.sort))
+        .transpose()?;
+    let (value, id) = cursor.unzip();
+    let limit = page.limit.map(|limit| limit.max(1) as usize);
+
+    let mut args = filter.arguments()?;
+    args.add(value).map_err(sqlx::Error::Encode)?;
+    args.add(id).map_err(sqlx::Error::Encode)?;
+    // 多取一行，用于判断是否还有下一页
+    args.add(limit.map(|limit| limit as i64 + 1)).map_err(sqlx::Error::Encode)?;
+    args.add(i64::from(page.offset.unwrap_or(0))).map_err(sqlx::Error::Encode)?;
+
+    // 总数与本页在同一快照中查询，不会因期间导入或删除任务而不一致
+    let stmt = job_page_stmt(page.sort, page.order, filter.metrics);
+    let mut trans = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY;")
+        .execute(&mut *trans)
+        .await?;
+    let total = sqlx::query_scalar_with::<_, i64, _>(JOB_COUNT_STMT, filter.arguments()?)
+        .fetch_one(&mut *trans)
+        .await?;
+    let mut jobs = sqlx::query_as_with::<_, JobInfo, _>(&stmt, args)
+        .fetch_all(&mut *trans)
+        .await?;
+    trans.commit().await?;
+    let next_cursor = match limit {
+        Some(limit) if jobs.len() > limit => {
+            jobs.truncate(limit);
+            jobs.last().map(|job| encode_cursor(page.sort, job))
+        }
+        _ => None,
+    };
+    Ok(JobPage {
+        total,
+        jobs,
+        next_cursor,
+    })
+}
+
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn find_job(job_id: i64, pool: State<'_, RwLock<PgPool>>) -> Result<JobInfo> {
-    let stmt = r#"
-        SELECT id, name, queue, num_cpu, parameters 
-        FROM job_info 
-        WHERE id = $1;"#;
     let pool = pool.read().await;
+    fetch_job(pool.deref(), job_id).await
+}
+
+pub async fn fetch_job(pool: &PgPool, job_id: i64) -> Result<JobInfo> {
+    let stmt = concat!(
+        "SELECT",
+        job_columns!(metrics),
+        job_source!(metrics),
+        r#"
+        WHERE j.id = $1;"#
+    );
     sqlx::query_as::<_, JobInfo>(stmt)
         .bind(job_id)
-        .fetch_one(pool.deref())
+        .fetch_one(pool)
         .await
         .map_err(Error::Sqlx)
 }
 
+/// 将任务移入回收站，误差日志等数据保留到彻底删除时
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn remove_job(job_id: i64, pool: State<'_, RwLock<PgPool>>, cache: State<'_, RwLock<Cache>>,) -> Result<()> {
     cache.write().await.remove(job_id);
-    let stmt = r#"
-        DELETE FROM job_info 
-        WHERE id = $1;"#;
     let pool = pool.read().await;
-    match sqlx::query(stmt).bind(job_id).execute(pool.deref()).await {
+    delete_job(pool.deref(), job_id).await
+}
+
+/// 移入回收站，已在回收站中的任务不变
+pub(crate) async fn delete_job(pool: &PgPool, job_id: i64) -> Result<()> {
+    let stmt = r#"
+        UPDATE job_info SET deleted_at = now()
+        WHERE id = $1 AND deleted_at IS NULL;"#;
+    match sqlx::query(stmt).bind(job_id).execute(pool).await {
         Ok(_) => Ok(()),
         Err(e) => Err(Error::Sqlx(e)),
     }
 }
+
+/// 从回收站恢复任务
+///
+/// 回收站期间重新导入了相同作业号的任务时返回唯一约束错误，
+/// 已开始彻底删除的任务返回 [`Error::JobPurging`]。
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn restore_job(job_id: i64, pool: State<'_, RwLock<PgPool>>) -> Result<()> {
+    let pool = pool.read().await;
+    undelete_job(pool.deref(), job_id).await
+}
+
+pub(crate) async fn undelete_job(pool: &PgPool, job_id: i64) -> Result<()> {
+    let stmt = r#"
+        UPDATE job_info SET deleted_at = NULL
+        WHERE id = $1 AND deleted_at IS NOT NULL AND NOT purging;"#;
+    let result = sqlx::query(stmt).bind(job_id).execute(pool).await?;
+    if result.rows_affected() > 0 {
+        return Ok(());
+    }
+    let stmt = "SELECT purging FROM job_info WHERE id = $1 AND deleted_at IS NOT NULL;";
+    match sqlx::query_scalar(stmt).bind(job_id).fetch_optional(pool).await? {
+        Some(true) => Err(Error::JobPurging(job_id)),
+        _ => Err(sqlx::Error::RowNotFound.into()),
+    }
+}
+
+/// 彻底删除的范围，均为空时为回收站中的全部任务
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct PurgeRequest {
+    /// 回收站中的这些任务
+    pub job_ids: Option<Vec<i64>>,
+    /// 只删除移入回收站超过该天数的任务
+    pub older_than_days: Option<u32>,
+}
+
+/// 回收站中满足条件的任务 id
+pub(crate) async fn fetch_purgeable(pool: &PgPool, request: &PurgeRequest) -> Result<Vec<i64>> {
+    let stmt = r#"
+        SELECT id FROM job_info
+        WHERE deleted_at IS NOT NULL
+            AND ($1::BIGINT[] IS NULL OR id = ANY($1))
+            AND ($2::INTEGER IS NULL OR deleted_at < now() - make_interval(days => $2))
+        ORDER BY id;"#;
+    let older_than_days = request.older_than_days.map(|days| days.min(i32::MAX as u32) as i32);
+    Ok(sqlx::query_scalar(stmt)
+        .bind(&request.job_ids)
+        .bind(older_than_days)
+        .fetch_all(pool)
+        .await?)
+}
+
+/// 彻底删除时每条语句删除的误差日志行数
+const PURGE_BATCH_ROWS: i64 = 10_000;
+
+/// 彻底删除回收站中的任务及其误差日志，任务已被恢复时返回 `false`
+///
+/// 先将任务标记为正在删除，此后 [`undelete_job`] 不再恢复该任务，再分批删除误差日志。
+/// 中途失败的任务仍留在回收站中，可以再次彻底删除。
+pub(crate) async fn purge_job(pool: &PgPool, job_id: i64) -> Result<bool> {
+    let mark_stmt = r#"
+        UPDATE job_info SET purging = TRUE
+        WHERE id = $1 AND deleted_at IS NOT NULL;"#;
+    let result = sqlx::query(mark_stmt).bind(job_id).execute(pool).await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let batch_stmt = r#"
+        DELETE FROM error_log
+        WHERE ctid IN (SELECT ctid FROM error_log WHERE job_id = $1 LIMIT $2);"#;
+    loop {
+        let result = sqlx::query(batch_stmt)
+            .bind(job_id)
+            .bind(PURGE_BATCH_ROWS)
+            .execute(pool)
+            .await?;
+        if result.rows_affected() < PURGE_BATCH_ROWS as u64 {
+            break;
+        }
+    }
+
+    let stmt = r#"
+        DELETE FROM job_info
+        WHERE id = $1 AND purging;"#;
+    let result = sqlx::query(stmt).bind(job_id).execute(pool).await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// 在后台逐个彻底删除回收站中的任务，立即返回将要删除的任务 id
+///
+/// 误差日志可能有数百万行，逐个任务分批删除，以免长时间占用连接与锁。
+/// 使用调用时连接池的副本，切换数据库后仍在原来的数据库中删除完，见 `switch_database`。
+/// 完成后发送 `jobs-purged` 事件，内容为实际删除的任务 id；
+/// 删除失败的任务以 [`BackgroundError`] 通知。
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn purge_jobs(request: Option<PurgeRequest>, app: AppHandle) -> Result<Vec<i64>> {
+    let pool = app.state::<RwLock<PgPool>>().read().await.clone();
+    let job_ids = fetch_purgeable(&pool, &request.unwrap_or_default()).await?;
+
+    let pending = job_ids.clone();
+    tokio::spawn(async move {
+        let mut purged = Vec::with_capacity(pending.len());
+        for job_id in pending {
+            match purge_job(&pool, job_id).await {
+                Ok(true) => purged.push(job_id),
+                Ok(false) => {}
+                Err(error) => BackgroundError {
+                    task: "purge",
+                    job_id: Some(job_id),
+                    error: &error,
+                }
+                .emit(&app),
+            }
+        }
+        let _ = app.emit("jobs-purged", &purged);
+    });
+    Ok(job_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(id: &str) -> (String, Option<String>, Option<i32>) {
+        let id = SchedulerId::parse(id, None).unwrap();
+        (id.scheduler, id.array_job_id, id.array_index)
+    }
+
+    #[test]
+    fn test_scheduler_id() {
+        assert_eq!(parse("12345"), (String::new(), None, None));
+        assert_eq!(parse("12345.pbs01"), ("pbs".into(), None, None));
+        assert_eq!(parse("123[4]"), ("pbs".into(), Some("123".into()), Some(4)));
+        assert_eq!(parse("123[4].pbs01"), ("pbs".into(), Some("123".into()), Some(4)));
+        assert_eq!(parse("123[]"), ("pbs".into(), None, None));
+        assert_eq!(parse("123_4"), ("slurm".into(), Some("123".into()), Some(4)));
+        assert_eq!(parse("123_[4]"), ("slurm".into(), Some("123".into()), Some(4)));
+
+        let id = SchedulerId::parse("12345", Some("Slurm")).unwrap();
+        assert_eq!(id.scheduler, "slurm");
+        assert_eq!(id.scheduler_id, "12345");
+
+        // 无法识别的作业号原样保存，括号不成对时不当作数组作业
+        let ids = ["abc", "12 345", "123[4", "123_[4", "123_4]", "123_", "123_[99999999999]", "123.-x"];
+        for id in ids {
+            assert_eq!(parse(id), (String::new(), None, None), "{id}");
+            assert_eq!(SchedulerId::parse(id, None).unwrap().scheduler_id, id);
+        }
+        assert_eq!(SchedulerId::parse("abc", Some("lsf")).unwrap().scheduler, "lsf");
+        for id in ["", " "] {
+            assert!(SchedulerId::parse(id, None).is_err(), "{id:?}");
+        }
+    }
+
+    #[test]
+    fn test_like_pattern() {
+        assert_eq!(like_pattern("100%_done\\"), "%100\\%\\_done\\\\%");
+        assert_eq!(like_pattern("mesh"), "%mesh%");
+    }
+
+    fn job(name: &str) -> JobInfo {
+        JobInfo {
+            id: 42,
+            name: name.into(),
+            queue: "q".into(),
+            num_cpu: 4,
+            parameters: None,
+            nodes: vec![],
+            scheduler: String::new(),
+            scheduler_id: "42".into(),
+            array_job_id: None,
+            array_index: None,
+            metadata: serde_json::Value::Null,
+            starred: false,
+            tags: vec![],
+            imported_at: None,
+            iterations: None,
+            load_steps: None,
+            failed_steps: None,
+            final_load: None,
+            wall_time: Some(12.5),
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn test_cursor() {
+        let cursor = "name:42:mesh:b";
+        assert_eq!(decode_cursor(cursor, JobSortKey::Name).unwrap(), ("mesh:b".into(), 42));
+        assert_eq!(decode_cursor("num_cpu:7:64", JobSortKey::NumCpu).unwrap(), ("64".into(), 7));
+        for cursor in ["", "name", "name:x:a", "num_cpu:7:many", "id:1"] {
+            assert!(
+                matches!(decode_cursor(cursor, JobSortKey::NumCpu), Err(Error::Cursor(_))),
+                "{cursor}"
+            );
+        }
+        assert!(decode_cursor(cursor, JobSortKey::Queue).is_err());
+
+        let at = NaiveDateTime::parse_from_str("2024-05-01 08:30:00.125", TIMESTAMP_FORMAT).unwrap();
+        let job = JobInfo {
+            imported_at: Some(at),
+            final_load: None,
+            ..job("j")
+        };
+        for sort in [JobSortKey::ImportedAt, JobSortKey::FinalLoad, JobSortKey::WallTime] {
+            let cursor = encode_cursor(sort, &job);
+            assert_eq!(decode_cursor(&cursor, sort).unwrap().1, job.id, "{cursor}");
+        }
+        assert!(decode_cursor("imported_at:1:yesterday", JobSortKey::ImportedAt).is_err());
+        // INTEGER 列的值超出 i32 时不应到数据库才报错
+        assert!(matches!(
+            decode_cursor("num_cpu:1:4294967296", JobSortKey::NumCpu),
+            Err(Error::Cursor(_))
+        ));
+        assert!(decode_cursor("iterations:1:4294967296", JobSortKey::Iterations).is_ok());
+    }
+}
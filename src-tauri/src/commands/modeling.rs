@@ -0,0 +1,146 @@
+use super::{job_columns, job_source, pg_arguments, query_as_and_send, JobInfo, Result};
+use sqlx::PgPool;
+use tauri::State;
+use tokio::sync::RwLock;
+
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+pub struct Modeling {
+    id: i32,
+    name: String,
+    desc: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+pub struct ModelingJob {
+    modeling_id: i32,
+    job_id: i64,
+}
+
+#[tauri::command]
+pub async fn get_modeling_list(
+    channel: tauri::ipc::Channel<Vec<u8>>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let stmt = r#"
+        SELECT id, name, "desc" 
+        FROM modeling 
+        ORDER BY id;"#;
+    let args = pg_arguments!();
+    query_as_and_send::<Modeling>(stmt, args, pool, channel).await
+}
+
+/// 新建模型，通过 `channel` 返回新建的 [`Modeling`]
+#[tauri::command]
+pub async fn create_modeling(
+    name: String,
+    desc: Option<String>,
+    channel: tauri::ipc::Channel<Vec<u8>>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let stmt = r#"
+        INSERT INTO modeling (name, "desc") 
+        VALUES ($1, $2) 
+        RETURNING id, name, "desc";"#;
+    let args = pg_arguments!(name, desc);
+    query_as_and_send::<Modeling>(stmt, args, pool, channel).await
+}
+
+/// 重命名模型，模型不存在时返回空列表
+#[tauri::command]
+pub async fn rename_modeling(
+    modeling_id: i32,
+    name: String,
+    channel: tauri::ipc::Channel<Vec<u8>>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let stmt = r#"
+        UPDATE modeling SET name = $2 
+        WHERE id = $1 
+        RETURNING id, name, "desc";"#;
+    let args = pg_arguments!(modeling_id, name);
+    query_as_and_send::<Modeling>(stmt, args, pool, channel).await
+}
+
+/// 修改模型描述，`desc` 为 `None` 时清除描述
+#[tauri::command]
+pub async fn describe_modeling(
+    modeling_id: i32,
+    desc: Option<String>,
+    channel: tauri::ipc::Channel<Vec<u8>>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let stmt = r#"
+        UPDATE modeling SET "desc" = $2 
+        WHERE id = $1 
+        RETURNING id, name, "desc";"#;
+    let args = pg_arguments!(modeling_id, desc);
+    query_as_and_send::<Modeling>(stmt, args, pool, channel).await
+}
+
+/// 删除模型，其下的任务关联随之级联删除，任务本身保留
+#[tauri::command]
+pub async fn remove_modeling(
+    modeling_id: i32,
+    channel: tauri::ipc::Channel<Vec<u8>>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let stmt = r#"
+        DELETE FROM modeling 
+        WHERE id = $1 
+        RETURNING id, name, "desc";"#;
+    let args = pg_arguments!(modeling_id);
+    query_as_and_send::<Modeling>(stmt, args, pool, channel).await
+}
+
+/// 将任务关联到模型，通过 `channel` 返回实际新增的关联（已存在的关联会被忽略）
+#[tauri::command]
+pub async fn attach_jobs(
+    modeling_id: i32,
+    job_ids: Vec<i64>,
+    channel: tauri::ipc::Channel<Vec<u8>>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let stmt = r#"
+        INSERT INTO modeling_jobs (modeling_id, job_id) 
+        SELECT $1, unnest($2::BIGINT[]) 
+        ON CONFLICT DO NOTHING 
+        RETURNING modeling_id, job_id;"#;
+    let args = pg_arguments!(modeling_id, job_ids);
+    query_as_and_send::<ModelingJob>(stmt, args, pool, channel).await
+}
+
+/// 解除任务与模型的关联，通过 `channel` 返回实际删除的关联
+#[tauri::command]
+pub async fn detach_jobs(
+    modeling_id: i32,
+    job_ids: Vec<i64>,
+    channel: tauri::ipc::Channel<Vec<u8>>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let stmt = r#"
+        DELETE FROM modeling_jobs 
+        WHERE modeling_id = $1 AND job_id = ANY($2) 
+        RETURNING modeling_id, job_id;"#;
+    let args = pg_arguments!(modeling_id, job_ids);
+    query_as_and_send::<ModelingJob>(stmt, args, pool, channel).await
+}
+
+/// 查询模型下的所有任务
+#[tauri::command]
+pub async fn get_modeling_jobs(
+    modeling_id: i32,
+    channel: tauri::ipc::Channel<Vec<u8>>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let stmt = concat!(
+        "SELECT",
+        job_columns!(metrics),
+        job_source!(metrics),
+        r#"
+        JOIN modeling_jobs m ON m.job_id = j.id 
+        WHERE m.modeling_id = $1 AND j.deleted_at IS NULL 
+        ORDER BY j.id;"#
+    );
+    let args = pg_arguments!(modeling_id);
+    query_as_and_send::<JobInfo>(stmt, args, pool, channel).await
+}
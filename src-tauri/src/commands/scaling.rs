@@ -0,0 +1,241 @@
+use super::{median, Result};
+use ahash::{AHashMap, AHashSet};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::ops::Deref;
+use tauri::State;
+use tokio::sync::RwLock;
+
+/// 参与扩展性分析的任务
+#[derive(Debug, sqlx::FromRow)]
+struct ScalingJob {
+    id: i64,
+    num_cpu: i32,
+    nodes: Vec<String>,
+    parameters: serde_json::Value,
+    total_time: f64,
+    iterations: i64,
+}
+
+impl ScalingJob {
+    /// 单次迭代的墙钟时间（秒）
+    fn time_per_iter(&self) -> Option<f64> {
+        (self.iterations > 0 && self.total_time > 0.0)
+            .then(|| self.total_time / self.iterations as f64)
+    }
+}
+
+/// 参数相同、CPU 数不同的一组任务
+#[derive(Debug, serde::Serialize)]
+pub struct ScalingGroup {
+    parameters: serde_json::Value,
+    /// 按 `num_cpu` 升序排列
+    points: Vec<ScalingPoint>,
+}
+
+/// 同一 CPU 数下的扩展性指标，以组内最少 CPU 数为基准
+#[derive(Debug, serde::Serialize)]
+pub struct ScalingPoint {
+    num_cpu: i32,
+    job_ids: Vec<i64>,
+    /// 单次迭代时间的中位数（秒）
+    time_per_iter: f64,
+    /// 加速比，即基准单次迭代时间与当前单次迭代时间之比
+    speedup: f64,
+    /// 并行效率，即 `speedup * 基准 CPU 数 / num_cpu`
+    efficiency: f64,
+}
+
+/// 计算节点相对同伴的快慢
+#[derive(Debug, serde::Serialize)]
+pub struct NodeReport {
+    node: String,
+    /// 参与比较的任务数
+    jobs: usize,
+    /// 所在任务单次迭代时间与同参数、同 CPU 数任务中位数之比的中位数
+    slowdown: f64,
+    /// 是否持续慢于同伴
+    slow: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ScalingReport {
+    groups: Vec<ScalingGroup>,
+    nodes: Vec<NodeReport>,
+}
+
+impl ScalingReport {
+    /// 判定节点持续偏慢所需的最少任务数
+    const MIN_NODE_JOBS: usize = 3;
+
+    fn new(jobs: &[ScalingJob], slow_threshold: f64) -> Self {
+        // 按参数分组，组内再按 CPU 数分桶
+        let mut groups = BTreeMap::<String, BTreeMap<i32, Vec<&ScalingJob>>>::new();
+        for job in jobs.iter().filter(|job| job.time_per_iter().is_some()) {
+            groups
+                .entry(job.parameters.to_string())
+                .or_default()
+                .entry(job.num_cpu)
+                .or_default()
+                .push(job);
+        }
+
+        let mut node_ratios = AHashMap::<&str, Vec<f64>>::new();
+        for buckets in groups.values() {
+            for bucket in buckets.values().filter(|bucket| bucket.len() > 1) {
+                let Some(peer) = median(bucket.iter().filter_map(|job| job.time_per_iter()).collect())
+                else {
+                    continue;
+                };
+                for job in bucket {
+                    let ratio = job.time_per_iter().unwrap_or(peer) / peer;
+                    // 同一节点可能在节点列表中重复出现
+                    for node in job.nodes.iter().collect::<AHashSet<_>>() {
+                        node_ratios.entry(node).or_default().push(ratio);
+                    }
+                }
+            }
+        }
+
+        let groups = groups
+            .into_values()
+            .filter(|buckets| buckets.len() > 1)
+            .filter_map(|buckets| {
+                let mut points = buckets
+                    .iter()
+                    .filter_map(|(&num_cpu, bucket)| {
+                        let time_per_iter =
+                            median(bucket.iter().filter_map(|job| job.time_per_iter()).collect())?;
+                        Some((num_cpu, bucket, time_per_iter))
+                    })
+                    .peekable();
+                let &(base_cpu, _, base_time) = points.peek()?;
+                let points = points
+                    .map(|(num_cpu, bucket, time_per_iter)| {
+                        let speedup = base_time / time_per_iter;
+                        ScalingPoint {
+                            num_cpu,
+                            job_ids: bucket.iter().map(|job| job.id).collect(),
+                            time_per_iter,
+                            speedup,
+                            efficiency: speedup * base_cpu as f64 / num_cpu as f64,
+                        }
+                    })
+                    .collect();
+                Some(ScalingGroup {
+                    parameters: buckets.values().next()?[0].parameters.clone(),
+                    points,
+                })
+            })
+            .collect();
+
+        let mut nodes = node_ratios
+            .into_iter()
+            .filter_map(|(node, ratios)| {
+                let jobs = ratios.len();
+                let slowdown = median(ratios)?;
+                Some(NodeReport {
+                    node: node.to_owned(),
+                    jobs,
+                    slowdown,
+                    slow: jobs >= Self::MIN_NODE_JOBS && slowdown > slow_threshold,
+                })
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| b.slowdown.total_cmp(&a.slowdown));
+
+        Self { groups, nodes }
+    }
+}
+
+/// 并行扩展性分析
+///
+/// 将参数相同、仅 CPU 数不同的任务归为一组，计算加速比和并行效率；
+/// 同时比较同参数、同 CPU 数的任务，找出持续慢于同伴的计算节点。
+///
+/// `modeling_id` 为空时分析所有任务；`slow_threshold` 为判定节点偏慢的
+/// 相对耗时阈值，默认为 `1.2`。没有参数的任务不参与分析。
+#[tauri::command]
+pub async fn get_scaling_analysis(
+    modeling_id: Option<i32>,
+    slow_threshold: Option<f64>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<ScalingReport> {
+    // 读取导入时计算的汇总指标，不扫描误差日志
+    let stmt = r#"
+        SELECT
+            j.id, j.num_cpu, j.nodes, j.parameters,
+            s.wall_time AS total_time, s.iterations
+        FROM job_info j
+        JOIN job_summary s ON s.job_id = j.id
+        WHERE j.parameters IS NOT NULL AND j.deleted_at IS NULL
+            AND ($1::INTEGER IS NULL OR j.id IN (SELECT job_id FROM modeling_jobs WHERE modeling_id = $1))
+        ORDER BY j.id;"#;
+
+    let pool = pool.read().await;
+    let jobs = sqlx::query_as::<_, ScalingJob>(stmt)
+        .bind(modeling_id)
+        .fetch_all(pool.deref())
+        .await?;
+
+    Ok(ScalingReport::new(&jobs, slow_threshold.unwrap_or(1.2)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn job(id: i64, num_cpu: i32, nodes: &[&str], mesh: i32, total_time: f64) -> ScalingJob {
+        ScalingJob {
+            id,
+            num_cpu,
+            nodes: nodes.iter().map(|&node| node.to_owned()).collect(),
+            parameters: json!({ "mesh": mesh }),
+            total_time,
+            iterations: 100,
+        }
+    }
+
+    #[test]
+    fn test_scaling_groups() {
+        let jobs = [
+            job(1, 4, &["a"], 1, 400.0),
+            job(2, 8, &["a", "b"], 1, 250.0),
+            job(3, 16, &["a", "b", "c", "d"], 1, 200.0),
+            // 只有一种 CPU 数，不构成分组
+            job(4, 4, &["a"], 2, 100.0),
+        ];
+        let report = ScalingReport::new(&jobs, 1.2);
+
+        assert_eq!(report.groups.len(), 1);
+        let points = &report.groups[0].points;
+        assert_eq!(points.iter().map(|p| p.num_cpu).collect::<Vec<_>>(), [4, 8, 16]);
+        assert_eq!(points[0].speedup, 1.0);
+        assert_eq!(points[0].efficiency, 1.0);
+        assert_eq!(points[1].speedup, 1.6);
+        assert_eq!(points[1].efficiency, 0.8);
+        assert_eq!(points[2].speedup, 2.0);
+        assert_eq!(points[2].efficiency, 0.5);
+    }
+
+    #[test]
+    fn test_slow_nodes() {
+        let jobs = [
+            job(1, 4, &["slow"], 1, 150.0),
+            job(2, 4, &["fast"], 1, 100.0),
+            job(3, 4, &["fast"], 1, 100.0),
+            job(4, 4, &["slow"], 2, 400.0),
+            job(5, 4, &["fast"], 2, 200.0),
+            job(6, 4, &["slow"], 3, 120.0),
+            job(7, 4, &["fast"], 3, 60.0),
+        ];
+        let report = ScalingReport::new(&jobs, 1.2);
+
+        assert_eq!(report.nodes[0].node, "slow");
+        assert_eq!(report.nodes[0].jobs, 3);
+        assert!(report.nodes[0].slow);
+        assert_eq!(report.nodes[1].node, "fast");
+        assert!(!report.nodes[1].slow);
+    }
+}
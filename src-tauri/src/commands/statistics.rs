@@ -0,0 +1,496 @@
+use super::{sibling_path, ExportFormat, LoadStep, Result, LOAD_STEP_STMT};
+use arrow::array::{ArrayRef, Float64Array, Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use rust_xlsxwriter::Workbook;
+use sqlx::PgPool;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "desktop")]
+use {std::ops::Deref, std::path::PathBuf, tauri::State, tokio::sync::RwLock};
+
+/// 样本分布的描述统计量，可直接用于绘制箱线图和直方图
+///
+/// 样本为空时所有统计量均为 `0`，`histogram` 为空
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Distribution {
+    count: usize,
+    min: f64,
+    q1: f64,
+    median: f64,
+    q3: f64,
+    max: f64,
+    mean: f64,
+    std_dev: f64,
+    histogram: Vec<HistogramBin>,
+}
+
+/// 直方图的一个区间 `[lower, upper)`，最后一个区间包含上界
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HistogramBin {
+    lower: f64,
+    upper: f64,
+    count: usize,
+}
+
+impl Distribution {
+    /// 由样本计算分布，忽略非有限值
+    pub(crate) fn new(mut values: Vec<f64>) -> Self {
+        values.retain(|v| v.is_finite());
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_by(f64::total_cmp);
+
+        let count = values.len();
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64;
+
+        Self {
+            count,
+            min: values[0],
+            q1: quantile(&values, 0.25),
+            median: quantile(&values, 0.5),
+            q3: quantile(&values, 0.75),
+            max: values[count - 1],
+            mean,
+            std_dev: variance.sqrt(),
+            histogram: histogram(&values),
+        }
+    }
+}
+
+/// 计算已排序样本的分位数（线性插值），`sorted` 不能为空
+pub(crate) fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
+
+/// 计算样本中位数，样本为空时返回 `None`
+pub(crate) fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    Some(quantile(&values, 0.5))
+}
+
+/// 按 Sturges 公式确定区间数，对已排序样本计算直方图
+fn histogram(sorted: &[f64]) -> Vec<HistogramBin> {
+    let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+    if min == max {
+        return vec![HistogramBin {
+            lower: min,
+            upper: max,
+            count: sorted.len(),
+        }];
+    }
+
+    let bins = (sorted.len() as f64).log2().ceil() as usize + 1;
+    let width = (max - min) / bins as f64;
+    let mut histogram = (0..bins)
+        .map(|i| HistogramBin {
+            lower: min + width * i as f64,
+            upper: min + width * (i + 1) as f64,
+            count: 0,
+        })
+        .collect::<Vec<_>>();
+    for value in sorted {
+        let idx = (((value - min) / width) as usize).min(bins - 1);
+        histogram[idx].count += 1;
+    }
+    histogram
+}
+
+/// 单个任务的汇总指标
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct JobStatistics {
+    job_id: i64,
+    num_cpu: i32,
+    /// 总求解时间（秒）
+    total_time: f64,
+    load_steps: usize,
+    failed_steps: usize,
+    iterations: i64,
+    /// 每次迭代消耗的 CPU 时间（秒），即 `total_time * num_cpu / iterations`
+    cpu_time_per_iter: Option<f64>,
+}
+
+impl JobStatistics {
+    /// 由同一任务按时间排序的加载步计算
+    pub(crate) fn new(job_id: i64, num_cpu: i32, steps: &[LoadStep]) -> Self {
+        let total_time = steps.iter().map(|step| step.cost).sum::<f64>();
+        let iterations = steps.iter().map(|step| step.iters as i64).sum::<i64>();
+        Self {
+            job_id,
+            num_cpu,
+            total_time,
+            load_steps: steps.len(),
+            failed_steps: steps.iter().filter(|step| !step.converged).count(),
+            iterations,
+            cpu_time_per_iter: (iterations > 0)
+                .then(|| total_time * num_cpu as f64 / iterations as f64),
+        }
+    }
+}
+
+/// 模型下所有任务的统计报告
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ModelingStatistics {
+    modeling_id: i32,
+    jobs: Vec<JobStatistics>,
+    /// 各任务总求解时间的分布
+    total_time: Distribution,
+    /// 所有加载步迭代次数的分布
+    iters_per_step: Distribution,
+    /// 各任务单次迭代 CPU 时间的分布
+    cpu_time_per_iter: Distribution,
+    /// 不收敛加载步占全部加载步的比例
+    failed_ratio: f64,
+}
+
+impl ModelingStatistics {
+    /// `jobs` 为 `(job_id, num_cpu)`，`steps` 须按任务和时间排序
+    fn new(modeling_id: i32, jobs: &[(i64, i32)], steps: &[LoadStep]) -> Self {
+        let jobs = jobs
+            .iter()
+            .map(|&(job_id, num_cpu)| {
+                let start = steps.partition_point(|step| step.job_id < job_id);
+                let end = steps.partition_point(|step| step.job_id <= job_id);
+                JobStatistics::new(job_id, num_cpu, &steps[start..end])
+            })
+            .collect::<Vec<_>>();
+
+        let total_steps = jobs.iter().map(|job| job.load_steps).sum::<usize>();
+        let failed_steps = jobs.iter().map(|job| job.failed_steps).sum::<usize>();
+
+        Self {
+            modeling_id,
+            total_time: Distribution::new(jobs.iter().map(|job| job.total_time).collect()),
+            iters_per_step: Distribution::new(steps.iter().map(|step| step.iters as f64).collect()),
+            cpu_time_per_iter: Distribution::new(
+                jobs.iter().filter_map(|job| job.cpu_time_per_iter).collect(),
+            ),
+            failed_ratio: match total_steps {
+                0 => 0.0,
+                n => failed_steps as f64 / n as f64,
+            },
+            jobs,
+        }
+    }
+}
+
+const JOB_HEADER: [&str; 7] = [
+    "job_id",
+    "num_cpu",
+    "total_time",
+    "load_steps",
+    "failed_steps",
+    "iterations",
+    "cpu_time_per_iter",
+];
+const DISTRIBUTION_HEADER: [&str; 9] = [
+    "metric", "count", "min", "q1", "median", "q3", "max", "mean", "std_dev",
+];
+
+impl ModelingStatistics {
+    /// 报告中的各项分布及其名称
+    fn distributions(&self) -> [(&'static str, &Distribution); 3] {
+        [
+            ("total_time", &self.total_time),
+            ("iters_per_step", &self.iters_per_step),
+            ("cpu_time_per_iter", &self.cpu_time_per_iter),
+        ]
+    }
+
+    /// 导出报告
+    ///
+    /// 各任务的指标写入 `path`，分布的描述统计量写入 `<name>_distribution`；
+    /// 格式为 `xlsx` 时分别写入工作表 `jobs` 与 `distribution`。
+    /// 直方图与不收敛比例不导出，后者可由各任务的加载步数计算。
+    pub(crate) fn write(&self, format: ExportFormat, path: &Path) -> Result<()> {
+        match format {
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_path(path)?;
+                writer.write_record(JOB_HEADER)?;
+                for job in &self.jobs {
+                    writer.serialize((
+                        job.job_id,
+                        job.num_cpu,
+                        job.total_time,
+                        job.load_steps,
+                        job.failed_steps,
+                        job.iterations,
+                        job.cpu_time_per_iter,
+                    ))?;
+                }
+                writer.flush()?;
+
+                let mut writer = csv::Writer::from_path(sibling_path(path, "distribution"))?;
+                writer.write_record(DISTRIBUTION_HEADER)?;
+                for (metric, dist) in self.distributions() {
+                    writer.serialize((
+                        metric,
+                        dist.count,
+                        dist.min,
+                        dist.q1,
+                        dist.median,
+                        dist.q3,
+                        dist.max,
+                        dist.mean,
+                        dist.std_dev,
+                    ))?;
+                }
+                writer.flush()?;
+            }
+            ExportFormat::Parquet => {
+                for (batch, path) in [
+                    (self.jobs_batch()?, path.to_owned()),
+                    (self.distribution_batch()?, sibling_path(path, "distribution")),
+                ] {
+                    let file = File::create(path)?;
+                    let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+                    writer.write(&batch)?;
+                    writer.close()?;
+                }
+            }
+            ExportFormat::Xlsx => {
+                let mut workbook = Workbook::new();
+                let sheet = workbook.add_worksheet().set_name("jobs")?;
+                sheet.write_row(0, 0, JOB_HEADER)?;
+                for (row, job) in (1..).zip(&self.jobs) {
+                    sheet.write(row, 0, job.job_id)?;
+                    sheet.write(row, 1, job.num_cpu)?;
+                    sheet.write(row, 2, job.total_time)?;
+                    sheet.write(row, 3, job.load_steps as i64)?;
+                    sheet.write(row, 4, job.failed_steps as i64)?;
+                    sheet.write(row, 5, job.iterations)?;
+                    if let Some(value) = job.cpu_time_per_iter {
+                        sheet.write(row, 6, value)?;
+                    }
+                }
+
+                let sheet = workbook.add_worksheet().set_name("distribution")?;
+                sheet.write_row(0, 0, DISTRIBUTION_HEADER)?;
+                for (row, (metric, dist)) in (1..).zip(self.distributions()) {
+                    sheet.write(row, 0, metric)?;
+                    sheet.write(row, 1, dist.count as i64)?;
+                    sheet.write_row(
+                        row,
+                        2,
+                        [dist.min, dist.q1, dist.median, dist.q3, dist.max, dist.mean, dist.std_dev],
+                    )?;
+                }
+                workbook.save(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn jobs_batch(&self) -> Result<RecordBatch> {
+        let schema = Schema::new(vec![
+            Field::new("job_id", DataType::Int64, false),
+            Field::new("num_cpu", DataType::Int32, false),
+            Field::new("total_time", DataType::Float64, false),
+            Field::new("load_steps", DataType::Int64, false),
+            Field::new("failed_steps", DataType::Int64, false),
+            Field::new("iterations", DataType::Int64, false),
+            Field::new("cpu_time_per_iter", DataType::Float64, true),
+        ]);
+        let jobs = || self.jobs.iter();
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(jobs().map(|j| j.job_id).collect::<Int64Array>()),
+            Arc::new(jobs().map(|j| j.num_cpu).collect::<Int32Array>()),
+            Arc::new(jobs().map(|j| j.total_time).collect::<Float64Array>()),
+            Arc::new(jobs().map(|j| j.load_steps as i64).collect::<Int64Array>()),
+            Arc::new(jobs().map(|j| j.failed_steps as i64).collect::<Int64Array>()),
+            Arc::new(jobs().map(|j| j.iterations).collect::<Int64Array>()),
+            Arc::new(jobs().map(|j| j.cpu_time_per_iter).collect::<Float64Array>()),
+        ];
+        Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+    }
+
+    fn distribution_batch(&self) -> Result<RecordBatch> {
+        let mut fields = vec![
+            Field::new("metric", DataType::Utf8, false),
+            Field::new("count", DataType::Int64, false),
+        ];
+        fields.extend(
+            DISTRIBUTION_HEADER[2..]
+                .iter()
+                .map(|name| Field::new(*name, DataType::Float64, false)),
+        );
+        let dists = self.distributions();
+        let stat = |f: fn(&Distribution) -> f64| -> ArrayRef {
+            Arc::new(dists.iter().map(|(_, dist)| f(dist)).collect::<Float64Array>())
+        };
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from_iter_values(dists.iter().map(|(metric, _)| *metric))),
+            Arc::new(dists.iter().map(|(_, dist)| dist.count as i64).collect::<Int64Array>()),
+            stat(|dist| dist.min),
+            stat(|dist| dist.q1),
+            stat(|dist| dist.median),
+            stat(|dist| dist.q3),
+            stat(|dist| dist.max),
+            stat(|dist| dist.mean),
+            stat(|dist| dist.std_dev),
+        ];
+        Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?)
+    }
+}
+
+/// 查询模型下的任务及其加载步，计算统计报告
+pub(crate) async fn fetch_modeling_statistics(
+    pool: &PgPool,
+    modeling_id: i32,
+) -> Result<ModelingStatistics> {
+    let stmt_jobs = r#"
+        SELECT j.id, j.num_cpu
+        FROM job_info j
+        JOIN modeling_jobs m ON m.job_id = j.id
+        WHERE m.modeling_id = $1 AND j.deleted_at IS NULL
+        ORDER BY j.id;"#;
+
+    let jobs = sqlx::query_as::<_, (i64, i32)>(stmt_jobs)
+        .bind(modeling_id)
+        .fetch_all(pool)
+        .await?;
+    let job_ids = jobs.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    let steps = sqlx::query_as::<_, LoadStep>(LOAD_STEP_STMT)
+        .bind(job_ids)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(ModelingStatistics::new(modeling_id, &jobs, &steps))
+}
+
+/// 汇总模型下所有任务的求解时间、迭代次数与收敛情况
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn get_modeling_statistics(
+    modeling_id: i32,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<ModelingStatistics> {
+    let pool = pool.read().await;
+    fetch_modeling_statistics(pool.deref(), modeling_id).await
+}
+
+/// 将模型的统计报告导出到文件，格式见 [`ModelingStatistics::write`]
+///
+/// `format` 为空时根据 `file` 的扩展名推断，支持 `csv`、`parquet` 与 `xlsx`。
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn export_modeling_statistics(
+    modeling_id: i32,
+    file: PathBuf,
+    format: Option<ExportFormat>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<()> {
+    let format = ExportFormat::resolve(format, &file)?;
+    let stats = {
+        let pool = pool.read().await;
+        fetch_modeling_statistics(pool.deref(), modeling_id).await?
+    };
+
+    tauri::async_runtime::spawn_blocking(move || stats.write(format, &file)).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(job_id: i64, iters: i32, cost: f64, converged: bool) -> LoadStep {
+        LoadStep {
+            job_id,
+            load: 0.0,
+            iters,
+            cost,
+            converged,
+        }
+    }
+
+    #[test]
+    fn test_distribution_empty() {
+        assert_eq!(Distribution::new(vec![]), Distribution::default());
+        assert_eq!(Distribution::new(vec![f64::NAN]), Distribution::default());
+    }
+
+    #[test]
+    fn test_distribution_quantiles() {
+        let dist = Distribution::new(vec![4.0, 1.0, 3.0, 2.0, 5.0]);
+        assert_eq!(dist.count, 5);
+        assert_eq!(dist.min, 1.0);
+        assert_eq!(dist.q1, 2.0);
+        assert_eq!(dist.median, 3.0);
+        assert_eq!(dist.q3, 4.0);
+        assert_eq!(dist.max, 5.0);
+        assert_eq!(dist.mean, 3.0);
+        assert!((dist.std_dev - 2f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_distribution_histogram() {
+        // 8 个样本 -> 4 个区间
+        let dist = Distribution::new((0..8).map(f64::from).collect());
+        assert_eq!(dist.histogram.len(), 4);
+        assert_eq!(dist.histogram.iter().map(|bin| bin.count).sum::<usize>(), 8);
+        // 最大值落入最后一个区间
+        assert_eq!(dist.histogram[3].upper, 7.0);
+        assert_eq!(dist.histogram[3].count, 2);
+
+        // 所有样本相同时只有一个区间
+        let dist = Distribution::new(vec![2.0; 3]);
+        assert_eq!(dist.histogram.len(), 1);
+        assert_eq!(dist.histogram[0].count, 3);
+    }
+
+    #[test]
+    fn test_modeling_statistics() {
+        let jobs = [(1, 4), (2, 8), (3, 2)];
+        let steps = [
+            step(1, 10, 20.0, true),
+            step(1, 30, 60.0, false),
+            step(1, 20, 40.0, true),
+            step(2, 10, 10.0, true),
+            step(2, 10, 10.0, true),
+        ];
+        let stats = ModelingStatistics::new(7, &jobs, &steps);
+
+        assert_eq!(stats.jobs.len(), 3);
+        assert_eq!(stats.jobs[0].total_time, 120.0);
+        assert_eq!(stats.jobs[0].iterations, 60);
+        assert_eq!(stats.jobs[0].failed_steps, 1);
+        assert_eq!(stats.jobs[0].cpu_time_per_iter, Some(8.0));
+        assert_eq!(stats.jobs[1].cpu_time_per_iter, Some(8.0));
+        // 没有日志的任务
+        assert_eq!(stats.jobs[2].load_steps, 0);
+        assert_eq!(stats.jobs[2].cpu_time_per_iter, None);
+
+        assert_eq!(stats.failed_ratio, 0.2);
+        assert_eq!(stats.iters_per_step.count, 5);
+        assert_eq!(stats.cpu_time_per_iter.count, 2);
+        assert_eq!(stats.total_time.count, 3);
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let stats = ModelingStatistics::new(7, &[(1, 4)], &[step(1, 10, 20.0, true)]);
+        let dir = std::env::temp_dir().join(format!("insight-statistics-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stats.csv");
+        stats.write(ExportFormat::Csv, &path).unwrap();
+
+        let jobs = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            jobs.lines().collect::<Vec<_>>(),
+            [JOB_HEADER.join(","), String::from("1,4,20.0,1,0,10,8.0")]
+        );
+        let dists = std::fs::read_to_string(dir.join("stats_distribution.csv")).unwrap();
+        assert_eq!(dists.lines().count(), 4);
+        assert!(dists.lines().nth(1).unwrap().starts_with("total_time,1,20.0"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
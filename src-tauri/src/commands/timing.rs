@@ -0,0 +1,193 @@
+use super::{LoadStep, Result, LOAD_STEP_STMT};
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+#[cfg(feature = "desktop")]
+use {std::ops::Deref, tauri::State, tokio::sync::RwLock};
+
+/// 相邻两条日志之间超过阈值的停顿，例如写检查点或排队暂停
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+pub struct IdleGap {
+    started_at: NaiveDateTime,
+    ended_at: NaiveDateTime,
+    /// 停顿时长（秒）
+    duration: f64,
+}
+
+/// 任务的时间构成，时长单位均为秒
+#[derive(Debug, serde::Serialize)]
+pub struct TimingReport {
+    started_at: Option<NaiveDateTime>,
+    ended_at: Option<NaiveDateTime>,
+    total_time: f64,
+    /// 收敛加载步的耗时
+    converged_time: f64,
+    /// 不收敛加载步的耗时
+    failed_time: f64,
+    idle_gaps: Vec<IdleGap>,
+    /// 所有停顿的总时长
+    idle_time: f64,
+    /// 按最近加载步的耗时估算的剩余时间，已达到目标载荷时为 `None`
+    remaining_time: Option<f64>,
+}
+
+impl TimingReport {
+    /// 估算剩余时间所参考的最近收敛加载步数
+    const RECENT_STEPS: usize = 5;
+
+    /// 按最近若干收敛加载步每单位载荷的耗时，估算从当前加载步到 `target_load` 的剩余时间
+    ///
+    /// `steps` 须按时间排序，最后一个加载步视为仍在进行中。已达到的载荷为最近一个收敛加载步的载荷，
+    /// 其后不收敛的重试与当前加载步已用的时间从估算结果中扣除。
+    fn remaining_time(steps: &[LoadStep], target_load: f64) -> Option<f64> {
+        let current = steps.last()?;
+        if current.load >= target_load {
+            return None;
+        }
+
+        let finished = &steps[..steps.len() - 1];
+        let mut converged = finished
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, step)| step.converged)
+            .take(Self::RECENT_STEPS + 1);
+        let (last, reached) = converged.next()?;
+        // 参考区间之前的收敛加载步，不足时从零载荷算起
+        let (start, base_load) = match converged.nth(Self::RECENT_STEPS - 1) {
+            Some((idx, step)) => (idx + 1, step.load),
+            None => (0, 0.0),
+        };
+
+        // 区间内所有加载步（含不收敛的重试）的耗时都计入推进这段载荷的代价
+        let cost = finished[start..=last].iter().map(|step| step.cost).sum::<f64>();
+        let advanced = reached.load - base_load;
+        if advanced <= 0.0 {
+            return None;
+        }
+
+        let spent = finished[last + 1..].iter().map(|step| step.cost).sum::<f64>() + current.cost;
+        let remaining = (target_load - reached.load) * cost / advanced;
+        Some((remaining - spent).max(0.0))
+    }
+}
+
+/// 任务耗时的详细分解
+///
+/// `gap_threshold` 为判定停顿的相邻日志间隔（秒），默认为 `60`；
+/// `target_load` 为估算剩余时间的目标载荷，默认为 `1.0`。
+#[cfg(feature = "desktop")]
+#[tauri::command]
+pub async fn get_timing_report(
+    job_id: i64,
+    gap_threshold: Option<f64>,
+    target_load: Option<f64>,
+    pool: State<'_, RwLock<PgPool>>,
+) -> Result<TimingReport> {
+    let pool = pool.read().await;
+    TimingReport::fetch(pool.deref(), job_id, gap_threshold, target_load).await
+}
+
+impl TimingReport {
+    pub async fn fetch(
+        pool: &PgPool,
+        job_id: i64,
+        gap_threshold: Option<f64>,
+        target_load: Option<f64>,
+    ) -> Result<Self> {
+        let stmt_range = r#"
+            SELECT
+                min(timestamp), max(timestamp)
+            FROM error_log
+            WHERE job_id = $1;"#;
+        let stmt_gaps = r#"
+            SELECT
+                started_at, ended_at, extract(EPOCH from ended_at - started_at)::DOUBLE PRECISION as duration
+            FROM (
+                SELECT timestamp as started_at, lead(timestamp) OVER (ORDER BY timestamp) as ended_at
+                FROM error_log
+                WHERE job_id = $1
+            ) t
+            WHERE ended_at - started_at > make_interval(secs => $2)
+            ORDER BY started_at;"#;
+
+        let ((started_at, ended_at), idle_gaps, steps) = tokio::try_join!(
+            sqlx::query_as::<_, (Option<NaiveDateTime>, Option<NaiveDateTime>)>(stmt_range)
+                .bind(job_id)
+                .fetch_one(pool),
+            sqlx::query_as::<_, IdleGap>(stmt_gaps)
+                .bind(job_id)
+                .bind(gap_threshold.unwrap_or(60.0))
+                .fetch_all(pool),
+            sqlx::query_as::<_, LoadStep>(LOAD_STEP_STMT)
+                .bind([job_id])
+                .fetch_all(pool),
+        )?;
+
+        let total_time = match (started_at, ended_at) {
+            (Some(start), Some(end)) => (end - start).as_seconds_f64(),
+            _ => 0.0,
+        };
+        let (converged, failed) = steps.iter().partition::<Vec<_>, _>(|step| step.converged);
+
+        Ok(Self {
+            started_at,
+            ended_at,
+            total_time,
+            converged_time: converged.iter().map(|step| step.cost).sum(),
+            failed_time: failed.iter().map(|step| step.cost).sum(),
+            idle_time: idle_gaps.iter().map(|gap| gap.duration).sum(),
+            idle_gaps,
+            remaining_time: Self::remaining_time(&steps, target_load.unwrap_or(1.0)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(load: f64, cost: f64, converged: bool) -> LoadStep {
+        LoadStep {
+            job_id: 1,
+            load,
+            iters: 1,
+            cost,
+            converged,
+        }
+    }
+
+    #[test]
+    fn test_remaining_time() {
+        // `load_steps` 视图的输出：0.4 不收敛后回退到 0.3，再次尝试 0.4 仍不收敛，正在第三次尝试
+        let steps = [
+            step(0.1, 20.0, true),
+            step(0.2, 20.0, true),
+            step(0.4, 40.0, false),
+            step(0.3, 20.0, true),
+            step(0.4, 10.0, false),
+            step(0.4, 5.0, true),
+        ];
+        // 到达 0.3 耗时 100 秒，即每单位载荷 1000 / 3 秒，已在 0.3 之后花费 15 秒
+        let remaining = TimingReport::remaining_time(&steps, 1.0).unwrap();
+        assert!((remaining - (0.7 * 1000.0 / 3.0 - 15.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_remaining_time_recent() {
+        // 只参考最近 5 个收敛加载步：从 0.1 推进到 0.6 耗时 50 秒
+        let mut steps = vec![step(0.1, 1000.0, true)];
+        steps.extend((2..=6).map(|i| step(0.1 * i as f64, 10.0, true)));
+        steps.push(step(0.7, 0.0, true));
+        let remaining = TimingReport::remaining_time(&steps, 1.0).unwrap();
+        assert!((remaining - 0.4 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_remaining_time_finished() {
+        let steps = [step(0.5, 10.0, true), step(1.0, 10.0, true)];
+        assert_eq!(TimingReport::remaining_time(&steps, 1.0), None);
+        // 没有已完成的加载步时无法估算
+        assert_eq!(TimingReport::remaining_time(&steps[..1], 1.0), None);
+        assert_eq!(TimingReport::remaining_time(&[], 1.0), None);
+    }
+}
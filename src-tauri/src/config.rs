@@ -1,48 +1,832 @@
 use super::error::Error;
+use super::secret::SecretStore;
 use super::Result;
-use std::path::Path;
+use lazy_static::lazy_static;
+use percent_encoding::percent_decode_str;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::RwLock;
+use std::time::Duration;
+use url::Url;
 
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
-pub(crate) struct AppConfig {
-    pub(crate) database: DatabaseConfig,
+/// 通过命令行参数指定的配置文件路径，优先于环境变量
+static PATH_OVERRIDE: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+lazy_static! {
+    /// 应用标识，取自 `tauri.conf.json` 的 `identifier`，
+    /// 使命令行工具与 Python 绑定使用与桌面应用相同的配置目录
+    static ref IDENTIFIER: String =
+        serde_json::from_str::<Value>(include_str!("../tauri.conf.json"))
+        .ok()
+        .and_then(|conf| conf["identifier"].as_str().map(str::to_owned))
+        .expect("tauri.conf.json has no identifier");
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct AppConfig {
+    /// 配置文件格式版本，旧版本的文件读取时自动升级
+    #[serde(default)]
+    version: u32,
+    /// 当前使用的数据库配置名称
+    pub(crate) active: String,
+    /// 命名的数据库配置，如生产、测试与共享服务器
+    pub(crate) profiles: BTreeMap<String, DatabaseConfig>,
+    #[serde(default)]
+    pub(crate) server: ServerConfig,
 }
 
 impl AppConfig {
     /// 配置文件名
     const FILENAME: &'static str = "config.json";
+    /// 指定配置文件路径的环境变量
+    pub const ENV: &'static str = "INSIGHT_CONFIG";
+    /// 当前配置文件格式版本
+    ///
+    /// - 0：无 `version` 字段，明文保存密码
+    /// - 1：密码保存在凭据存储中
+    /// - 2：`database` 改为多个命名的配置 `profiles`，`active` 为当前使用的配置
+    const VERSION: u32 = 2;
+    /// 从旧版本升级时单个数据库配置的名称
+    const DEFAULT_PROFILE: &'static str = "default";
+
+    /// 指定配置文件路径，优先于环境变量 [`ENV`](Self::ENV) 与默认路径
+    pub fn set_path(path: impl Into<PathBuf>) {
+        *PATH_OVERRIDE.write().unwrap_or_else(|e| e.into_inner()) = Some(path.into());
+    }
+
+    /// 配置文件路径，默认为应用配置目录下的 `config.json`
+    pub fn path() -> Result<PathBuf> {
+        if let Some(path) = PATH_OVERRIDE
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+        {
+            return Ok(path);
+        }
+        match std::env::var_os(Self::ENV) {
+            Some(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+            _ => Self::default_path(),
+        }
+    }
+
+    /// 系统没有应用配置目录时（如未设置 `HOME`）返回错误，而不是使用当前目录
+    fn default_path() -> Result<PathBuf> {
+        dirs::config_dir()
+            .map(|dir| dir.join(IDENTIFIER.as_str()).join(Self::FILENAME))
+            .ok_or(Error::ConfigDir)
+    }
 
-    pub(crate) fn load() -> Result<AppConfig> {
-        let path = Path::new(Self::FILENAME);
+    /// 读取配置，不存在时写入示例配置
+    ///
+    /// 旧版本的配置文件会被升级并写回，明文保存的密码会被移入凭据存储。
+    pub fn load() -> Result<AppConfig> {
+        let path = Self::path()?;
         if !path.exists() {
-            std::fs::write(path, include_str!("../config.example.json"))?;
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let default = Self::default_path().is_ok_and(|default| default == path);
+            if default && Path::new(Self::FILENAME).exists() {
+                Self::migrate_from_cwd(&path)?;
+            } else {
+                std::fs::write(&path, include_str!("../config.example.json"))?;
+            }
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let mut config = Self::parse(&content).map_err(|errors| Error::Config {
+            path: path.clone(),
+            errors,
+        })?;
+        let plaintext = config.profiles.values().any(|db| !db.password.is_empty());
+        if config.version < Self::VERSION || plaintext {
+            config.upgrade()?;
+            config.save()?;
+        }
+        Ok(config)
+    }
+
+    /// 旧版本的配置文件与加密的密码保存在当前目录下，复制到应用配置目录
+    fn migrate_from_cwd(path: &Path) -> Result<()> {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for name in [Self::FILENAME, SecretStore::FILENAME, SecretStore::KEY_FILE] {
+            if Path::new(name).exists() {
+                std::fs::copy(name, dir.join(name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 解析并校验配置，返回所有字段错误
+    ///
+    /// 旧版本的结构先在 JSON 层面升级，`version` 保持不变，由 [`upgrade`](Self::upgrade) 完成其余步骤。
+    fn parse(content: &str) -> std::result::Result<AppConfig, Vec<FieldError>> {
+        let mut value: Value =
+            serde_json::from_str(content).map_err(|e| vec![FieldError::new(".", e.to_string())])?;
+        Self::upgrade_json(&mut value);
+        let config: AppConfig = serde_path_to_error::deserialize(&value).map_err(|e| {
+            let errors = Self::deserialize_errors(&value);
+            if errors.is_empty() {
+                vec![FieldError::nested("", &e)]
+            } else {
+                errors
+            }
+        })?;
+
+        config.check()?;
+        Ok(config)
+    }
+
+    /// 整体反序列化失败时，逐个字段与逐个数据库配置分别反序列化，收集所有出错的字段
+    ///
+    /// 同一个数据库配置中只能报告第一个错误。
+    fn deserialize_errors(value: &Value) -> Vec<FieldError> {
+        fn check<T: DeserializeOwned>(
+            field: &str,
+            value: Option<&Value>,
+            required: bool,
+            errors: &mut Vec<FieldError>,
+        ) {
+            match value {
+                Some(value) => {
+                    if let Err(e) = serde_path_to_error::deserialize::<_, T>(value) {
+                        errors.push(FieldError::nested(field, &e));
+                    }
+                }
+                None if required => errors.push(FieldError::new(field, "missing field")),
+                None => {}
+            }
+        }
+
+        let Some(object) = value.as_object() else {
+            return Vec::new();
+        };
+        let mut errors = Vec::new();
+        check::<u32>("version", object.get("version"), false, &mut errors);
+        check::<String>("active", object.get("active"), true, &mut errors);
+        check::<ServerConfig>("server", object.get("server"), false, &mut errors);
+        match object.get("profiles") {
+            Some(Value::Object(profiles)) => {
+                for (name, database) in profiles {
+                    let field = format!("profiles.{name}");
+                    check::<DatabaseConfig>(&field, Some(database), true, &mut errors);
+                }
+            }
+            profiles => check::<BTreeMap<String, DatabaseConfig>>(
+                "profiles",
+                profiles,
+                true,
+                &mut errors,
+            ),
+        }
+        errors
+    }
+
+    /// 升级旧版本配置文件的结构
+    fn upgrade_json(value: &mut Value) {
+        let Some(object) = value.as_object_mut() else {
+            return;
+        };
+        let version = object.get("version").and_then(Value::as_u64).unwrap_or(0);
+        if version < 2 {
+            if let Some(database) = object.remove("database") {
+                let profiles =
+                    serde_json::Map::from_iter([(Self::DEFAULT_PROFILE.to_owned(), database)]);
+                object.insert("profiles".to_owned(), Value::Object(profiles));
+                object.insert("active".to_owned(), Self::DEFAULT_PROFILE.into());
+            }
+        }
+    }
+
+    /// 校验配置，返回所有字段错误
+    pub(crate) fn check(&self) -> std::result::Result<(), Vec<FieldError>> {
+        let errors = self.validate();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.version > Self::VERSION {
+            errors.push(FieldError::new(
+                "version",
+                format!(
+                    "version {} is newer than supported version {}",
+                    self.version,
+                    Self::VERSION
+                ),
+            ));
+        }
+        if self.profiles.is_empty() {
+            errors.push(FieldError::new("profiles", "must not be empty"));
+        } else if !self.profiles.contains_key(&self.active) {
+            errors.push(FieldError::new(
+                "active",
+                format!("profile '{}' not found", self.active),
+            ));
+        }
+        for (name, database) in &self.profiles {
+            if name.trim().is_empty() {
+                errors.push(FieldError::new(
+                    "profiles",
+                    "profile name must not be empty",
+                ));
+            }
+            database.validate(&format!("profiles.{name}"), &mut errors);
+        }
+        if self.server.port == 0 {
+            errors.push(FieldError::new("server.port", "must not be 0"));
+        }
+        errors
+    }
+
+    /// 逐个版本升级到 [`VERSION`](Self::VERSION)
+    fn upgrade(&mut self) -> Result<()> {
+        // 1：密码移入凭据存储。手动写入配置文件的明文密码也一并处理
+        for database in self.profiles.values_mut() {
+            database.store_password()?;
         }
+        // 2：结构已在 `upgrade_json` 中升级
+        self.version = Self::VERSION;
+        Ok(())
+    }
 
-        let config = std::fs::read_to_string(Self::FILENAME)?;
-        serde_json::from_str(&config).map_err(Error::SerdeJson)
+    /// 当前使用的数据库配置
+    pub fn database(&self) -> Result<&DatabaseConfig> {
+        self.profiles
+            .get(&self.active)
+            .ok_or_else(|| Error::ProfileNotFound(self.active.clone()))
     }
 
+    /// 写入配置文件，密码只保存在凭据存储中
     pub(crate) fn save(&self) -> Result<()> {
-        let path = Path::new(Self::FILENAME);
-        let config = serde_json::to_string_pretty(self)?;
+        let path = Self::path()?;
+        let mut config = self.clone();
+        config.version = Self::VERSION;
+        for database in config.profiles.values_mut() {
+            database.password.clear();
+            database.has_password = false;
+        }
+        let config = serde_json::to_string_pretty(&config)?;
         std::fs::write(path, config)?;
         Ok(())
     }
+
+    /// 配置文件所在目录
+    pub(crate) fn dir() -> Result<PathBuf> {
+        let path = Self::path()?;
+        Ok(path.parent().unwrap_or(Path::new(".")).to_owned())
+    }
+
+    /// 凭据存储，系统凭据管理不可用时加密文件与配置文件放在同一目录，
+    /// 密钥放在本地数据目录（如 `~/.local/share/ixuejie.insight/`），没有时与加密文件放在一起
+    pub(crate) fn secrets() -> Result<SecretStore> {
+        let dir = Self::dir()?;
+        let key_dir = dirs::data_local_dir()
+            .map_or_else(|| dir.clone(), |data| data.join(IDENTIFIER.as_str()));
+        Ok(SecretStore::new(&dir, &key_dir))
+    }
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
-pub(crate) struct DatabaseConfig {
+/// 数据库配置的增删改与切换
+#[cfg_attr(not(feature = "desktop"), allow(dead_code))]
+impl AppConfig {
+    fn profile_mut(&mut self, name: &str) -> Result<&mut DatabaseConfig> {
+        self.profiles
+            .get_mut(name)
+            .ok_or_else(|| Error::ProfileNotFound(name.to_owned()))
+    }
+
+    /// 添加数据库配置，名称不可重复
+    pub(crate) fn add_profile(&mut self, name: &str, database: DatabaseConfig) -> Result<()> {
+        if self.profiles.contains_key(name) {
+            return Err(Error::ProfileExists(name.to_owned()));
+        }
+        self.profiles.insert(name.to_owned(), database);
+        Ok(())
+    }
+
+    /// 修改数据库配置，`rename` 不为空时同时重命名
+    ///
+    /// `database` 中密码为空且未指定 `password_secret` 时沿用原来的密码。
+    pub(crate) fn edit_profile(
+        &mut self,
+        name: &str,
+        mut database: DatabaseConfig,
+        rename: Option<&str>,
+    ) -> Result<()> {
+        let new_name = rename.unwrap_or(name);
+        if new_name != name && self.profiles.contains_key(new_name) {
+            return Err(Error::ProfileExists(new_name.to_owned()));
+        }
+        let old = self.profile_mut(name)?;
+        if database.password.is_empty() && database.password_secret.is_none() {
+            database.password_secret = old.password_secret.clone();
+        }
+
+        self.profiles.remove(name);
+        self.profiles.insert(new_name.to_owned(), database);
+        if self.active == name {
+            self.active = new_name.to_owned();
+        }
+        Ok(())
+    }
+
+    /// 删除数据库配置，当前使用的配置不可删除
+    ///
+    /// 返回其密码在凭据存储中的名称，仅当没有其它配置共用时。
+    pub(crate) fn remove_profile(&mut self, name: &str) -> Result<Option<String>> {
+        if self.active == name {
+            return Err(Error::ActiveProfile(name.to_owned()));
+        }
+        let removed = self
+            .profiles
+            .remove(name)
+            .ok_or_else(|| Error::ProfileNotFound(name.to_owned()))?;
+        Ok(removed.password_secret.filter(|secret| {
+            self.profiles
+                .values()
+                .all(|db| db.password_secret.as_ref() != Some(secret))
+        }))
+    }
+
+    /// 切换当前使用的数据库配置
+    pub(crate) fn activate(&mut self, name: &str) -> Result<()> {
+        self.profile_mut(name)?;
+        self.active = name.to_owned();
+        Ok(())
+    }
+}
+
+/// 配置文件中某个字段的错误
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FieldError {
+    /// 字段路径，如 `database.port`
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_owned(),
+            message: message.into(),
+        }
+    }
+
+    /// `prefix` 之下的反序列化错误，字段路径为 `prefix` 与错误位置相连
+    fn nested(prefix: &str, e: &serde_path_to_error::Error<serde_json::Error>) -> Self {
+        let path = e.path().to_string();
+        let field = match (prefix, path.as_str()) {
+            ("", path) => path.to_owned(),
+            (prefix, ".") => prefix.to_owned(),
+            (prefix, path) => format!("{prefix}.{path}"),
+        };
+        Self::new(&field, e.inner().to_string())
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// 数据库连接配置
+///
+/// 可以填写单独的字段，也可以填写连接地址 `url`，两者同时设置时以非空的单独字段为准。
+/// 密码不保存在配置文件中，见 [`store_password`](Self::store_password)。
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct DatabaseConfig {
+    /// 连接地址，如 `postgres://insight@localhost:5432/insight?sslmode=require`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(default)]
     user: String,
+    /// 明文密码，仅用于与前端交换，以及兼容旧版本的配置文件，不会写入配置文件
+    ///
+    /// 前端传回空字符串表示密码不变。
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     password: String,
+    /// 密码在凭据存储中的名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password_secret: Option<String>,
+    /// 凭据存储中是否有密码，只发送给前端，不会从前端读取或写入配置文件
+    #[serde(default, skip_deserializing, skip_serializing_if = "std::ops::Not::not")]
+    has_password: bool,
+    #[serde(default)]
     host: String,
-    port: u16,
+    /// 默认为 5432
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+    #[serde(default)]
     database: String,
+    /// TLS 模式，同 libpq 的 `sslmode`，默认为 `prefer`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ssl_mode: Option<SslMode>,
+    /// 校验服务器证书的根证书（PEM），用于 `verify-ca` 与 `verify-full`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ssl_root_cert: Option<PathBuf>,
+    /// 在 `pg_stat_activity` 中显示的应用名称，默认为 `insight`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    application_name: Option<String>,
+    /// 连接池的最大连接数，默认为 10
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_connections: Option<u32>,
+    /// 连接池保持的最小连接数，默认为 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_connections: Option<u32>,
+    /// 建立连接或从连接池获取连接的超时时间（秒），默认为 30
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    connect_timeout: Option<u64>,
+    /// 单条语句的超时时间（毫秒），即 `statement_timeout`，默认不限制
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    statement_timeout: Option<u64>,
+}
+
+/// TLS 模式，含义同 libpq
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    Allow,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl From<SslMode> for PgSslMode {
+    fn from(mode: SslMode) -> Self {
+        match mode {
+            SslMode::Disable => PgSslMode::Disable,
+            SslMode::Allow => PgSslMode::Allow,
+            SslMode::Prefer => PgSslMode::Prefer,
+            SslMode::Require => PgSslMode::Require,
+            SslMode::VerifyCa => PgSslMode::VerifyCa,
+            SslMode::VerifyFull => PgSslMode::VerifyFull,
+        }
+    }
 }
 
 impl DatabaseConfig {
-    pub(crate) fn url(&self) -> String {
-        format!(
-            "postgres://{}:{}@{}:{}/{}",
-            &self.user, &self.password, &self.host, &self.port, &self.database
+    const APPLICATION_NAME: &'static str = "insight";
+
+    /// 只使用连接地址的配置
+    pub fn from_url(url: impl Into<String>) -> Self {
+        Self {
+            url: Some(url.into()),
+            ..Default::default()
+        }
+    }
+
+    /// 不含密码的连接参数
+    fn base_options(&self) -> Result<PgConnectOptions> {
+        let mut options = match &self.url {
+            Some(url) => PgConnectOptions::from_str(url)?,
+            None => PgConnectOptions::new(),
+        };
+        if !self.host.is_empty() {
+            options = options.host(&self.host);
+        }
+        if let Some(port) = self.port {
+            options = options.port(port);
+        }
+        if !self.user.is_empty() {
+            options = options.username(&self.user);
+        }
+        if !self.database.is_empty() {
+            options = options.database(&self.database);
+        }
+        if let Some(mode) = self.ssl_mode {
+            options = options.ssl_mode(mode.into());
+        }
+        if let Some(cert) = &self.ssl_root_cert {
+            options = options.ssl_root_cert(cert);
+        }
+        let application_name = self.application_name.as_deref();
+        if application_name.is_some() || options.get_application_name().is_none() {
+            options = options.application_name(application_name.unwrap_or(Self::APPLICATION_NAME));
+        }
+        if let Some(timeout) = self.statement_timeout {
+            options = options.options([("statement_timeout", timeout.to_string())]);
+        }
+        Ok(options)
+    }
+
+    /// 连接参数，密码从凭据存储中读取，可能阻塞
+    ///
+    /// 各字段分别传给驱动，密码中的 `@`、`/` 等字符无需转义。
+    pub fn connect_options(&self) -> Result<PgConnectOptions> {
+        let options = self.base_options()?;
+        let password = self.password()?;
+        Ok(if password.is_empty() {
+            options
+        } else {
+            options.password(&password)
+        })
+    }
+
+    /// 连接池参数
+    pub fn pool_options(&self) -> PgPoolOptions {
+        let mut options = PgPoolOptions::new();
+        if let Some(max) = self.max_connections {
+            options = options.max_connections(max);
+        }
+        if let Some(min) = self.min_connections {
+            options = options.min_connections(min);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            options = options.acquire_timeout(Duration::from_secs(timeout));
+        }
+        options
+    }
+
+    /// 按配置创建连接池，读取密码可能阻塞
+    pub async fn connect(&self) -> Result<PgPool> {
+        let options = self.connect_options()?;
+        Ok(self.pool_options().connect_with(options).await?)
+    }
+
+    /// 校验各字段，`prefix` 为字段路径前缀
+    fn validate(&self, prefix: &str, errors: &mut Vec<FieldError>) {
+        let field = |name: &str| format!("{prefix}.{name}");
+        match &self.url {
+            Some(url) => {
+                let scheme = url.split_once("://").map(|(scheme, _)| scheme);
+                if !matches!(scheme, Some("postgres" | "postgresql")) {
+                    errors.push(FieldError::new(
+                        &field("url"),
+                        "must start with postgres:// or postgresql://",
+                    ));
+                } else if let Err(e) = PgConnectOptions::from_str(url) {
+                    errors.push(FieldError::new(&field("url"), e.to_string()));
+                }
+            }
+            // 没有连接地址时必须填写
+            None => {
+                for (name, value) in [
+                    ("host", &self.host),
+                    ("user", &self.user),
+                    ("database", &self.database),
+                ] {
+                    if value.trim().is_empty() {
+                        errors.push(FieldError::new(&field(name), "must not be empty"));
+                    }
+                }
+            }
+        }
+        if self.port == Some(0) {
+            errors.push(FieldError::new(&field("port"), "must not be 0"));
+        }
+        if self.max_connections == Some(0) {
+            errors.push(FieldError::new(&field("max_connections"), "must not be 0"));
+        }
+        if let (Some(min), Some(max)) = (self.min_connections, self.max_connections) {
+            if min > max {
+                errors.push(FieldError::new(
+                    &field("min_connections"),
+                    "must not be greater than max_connections",
+                ));
+            }
+        }
+        if let Some(cert) = &self.ssl_root_cert {
+            if !cert.is_file() {
+                errors.push(FieldError::new(
+                    &field("ssl_root_cert"),
+                    format!("file not found: {}", cert.display()),
+                ));
+            }
+        }
+    }
+
+    /// 当前密码，未设置时为空字符串
+    fn password(&self) -> Result<String> {
+        if !self.password.is_empty() {
+            return Ok(self.password.clone());
+        }
+        match &self.password_secret {
+            Some(name) => Ok(AppConfig::secrets()?.get(name)?.unwrap_or_default()),
+            None => Ok(String::new()),
+        }
+    }
+
+    /// 检查凭据存储中是否有密码并记录在 `has_password`，用于发送给前端
+    ///
+    /// 密码本身不会发送给前端，前端只在修改密码时填写 `password`。
+    #[cfg_attr(not(feature = "desktop"), allow(dead_code))]
+    pub(crate) fn check_password(&mut self) -> Result<()> {
+        self.has_password = !self.password()?.is_empty();
+        self.password.clear();
+        Ok(())
+    }
+
+    /// 连接地址中的密码移到 `password`，避免明文写入配置文件
+    fn take_url_password(&mut self) -> Result<()> {
+        let Some(url) = &self.url else {
+            return Ok(());
+        };
+        let mut url = Url::parse(url).map_err(|e| sqlx::Error::Configuration(Box::new(e)))?;
+        if let Some(password) = url.password() {
+            if self.password.is_empty() {
+                self.password = percent_decode_str(password)
+                    .decode_utf8_lossy()
+                    .into_owned();
+            }
+            // 只有 cannot-be-a-base 的地址会失败，此时不会有密码
+            let _ = url.set_password(None);
+            self.url = Some(url.into());
+        }
+        Ok(())
+    }
+
+    /// 将 `password` 保存到凭据存储并记录其名称，`password` 为空时不做修改
+    pub(crate) fn store_password(&mut self) -> Result<()> {
+        self.take_url_password()?;
+        if self.password.is_empty() {
+            return Ok(());
+        }
+        let options = self.base_options()?;
+        let name = format!(
+            "postgres://{}@{}:{}/{}",
+            options.get_username(),
+            options.get_host(),
+            options.get_port(),
+            options.get_database().unwrap_or_default()
+        );
+        AppConfig::secrets()?.set(&name, &self.password)?;
+        self.password_secret = Some(name);
+        Ok(())
+    }
+}
+
+/// 本地 HTTP 接口配置，仅监听 `127.0.0.1`
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct ServerConfig {
+    pub(crate) enabled: bool,
+    pub(crate) port: u16,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7878,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(content: &str) -> Vec<String> {
+        AppConfig::parse(content)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.field)
+            .collect()
+    }
+
+    fn database(port: u16) -> DatabaseConfig {
+        DatabaseConfig {
+            user: "u".to_owned(),
+            password_secret: Some(format!("secret-{port}")),
+            host: "h".to_owned(),
+            port: Some(port),
+            database: "d".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_config() {
+        assert!(!IDENTIFIER.is_empty());
+        let config = AppConfig::parse(include_str!("../config.example.json")).unwrap();
+        assert_eq!(config.version, AppConfig::VERSION);
+        assert_eq!(config.database().unwrap().port, Some(5432));
+        assert!(!config.server.enabled);
+
+        // 版本 0 没有 `version` 与 `server`，只有一个 `database`
+        let config = AppConfig::parse(
+            r#"{"database": {"user": "u", "password": "p", "host": "h", "port": 5433, "database": "d"}}"#,
         )
+        .unwrap();
+        assert_eq!(config.version, 0);
+        assert_eq!(config.active, AppConfig::DEFAULT_PROFILE);
+        assert_eq!(config.database().unwrap().port, Some(5433));
+        assert_eq!(config.server.port, 7878);
+    }
+
+    #[test]
+    fn test_field_errors() {
+        assert_eq!(
+            fields(r#"{"database": {"user": "u", "host": "h", "port": "5432", "database": "d"}}"#),
+            ["profiles.default.port"]
+        );
+        assert_eq!(fields(r#"{"version": 2, "active": "a"}"#), ["profiles"]);
+        assert_eq!(fields("{"), ["."]);
+        assert_eq!(
+            fields(
+                r#"{"version": "2", "active": 1, "server": {"port": "p"}, "profiles": {"a": {"port": "x"}, "b": {"port": -1}}}"#
+            ),
+            ["version", "active", "server.port", "profiles.a.port", "profiles.b.port"]
+        );
+        assert_eq!(
+            fields(
+                r#"{"version": 99, "active": "b", "profiles": {"a": {"user": "", "host": " ", "port": 0, "database": "d"}}}"#
+            ),
+            [
+                "version",
+                "active",
+                "profiles.a.host",
+                "profiles.a.user",
+                "profiles.a.port"
+            ]
+        );
+        assert_eq!(
+            fields(
+                r#"{"active": "a", "profiles": {"a": {"url": "mysql://h/d", "max_connections": 2, "min_connections": 4}}}"#
+            ),
+            ["profiles.a.url", "profiles.a.min_connections"]
+        );
+    }
+
+    #[test]
+    fn test_connect_options() {
+        let database = DatabaseConfig {
+            url: Some("postgres://a@b:6543/c?sslmode=require&application_name=x".to_owned()),
+            database: "d".to_owned(),
+            statement_timeout: Some(5000),
+            ..Default::default()
+        };
+        let options = database.base_options().unwrap();
+        assert_eq!(options.get_username(), "a");
+        assert_eq!(options.get_host(), "b");
+        assert_eq!(options.get_port(), 6543);
+        assert_eq!(options.get_database(), Some("d"));
+        assert!(matches!(options.get_ssl_mode(), PgSslMode::Require));
+        assert_eq!(options.get_application_name(), Some("x"));
+        assert_eq!(options.get_options(), Some("-c statement_timeout=5000"));
+
+        let options = database.connect_options().unwrap();
+        assert_eq!(options.get_port(), 6543);
+    }
+
+    #[test]
+    fn test_take_url_password() {
+        let mut database = DatabaseConfig {
+            url: Some("postgres://u:p%40ss%2Fword@h/d".to_owned()),
+            ..Default::default()
+        };
+        database.take_url_password().unwrap();
+        assert_eq!(database.password, "p@ss/word");
+        assert_eq!(database.url.as_deref(), Some("postgres://u@h/d"));
+    }
+
+    #[test]
+    fn test_profiles() {
+        let mut config = AppConfig::parse(include_str!("../config.example.json")).unwrap();
+        config.add_profile("sandbox", database(1)).unwrap();
+        config.add_profile("shared", database(2)).unwrap();
+        assert!(matches!(
+            config.add_profile("shared", database(2)),
+            Err(Error::ProfileExists(_))
+        ));
+
+        // 重命名当前配置，未提供密码时沿用原来的
+        config.activate("sandbox").unwrap();
+        let mut edited = database(3);
+        edited.password_secret = None;
+        config
+            .edit_profile("sandbox", edited, Some("test"))
+            .unwrap();
+        assert_eq!(config.active, "test");
+        let active = config.database().unwrap();
+        assert_eq!(active.port, Some(3));
+        assert_eq!(active.password_secret.as_deref(), Some("secret-1"));
+
+        assert!(matches!(
+            config.remove_profile("test"),
+            Err(Error::ActiveProfile(_))
+        ));
+        assert_eq!(
+            config.remove_profile("shared").unwrap().as_deref(),
+            Some("secret-2")
+        );
+        assert!(matches!(
+            config.activate("shared"),
+            Err(Error::ProfileNotFound(_))
+        ));
+        assert!(config.check().is_ok());
     }
 }
@@ -1,3 +1,8 @@
+mod catalog;
+
+pub use catalog::Locale;
+use serde::ser::SerializeStruct;
+use serde_json::{Map, Value};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -11,24 +16,400 @@ pub enum Error {
     #[error(transparent)]
     Sqlx(#[from] sqlx::Error),
 
+    #[cfg(feature = "desktop")]
     #[error(transparent)]
     Tauri(#[from] tauri::Error),
 
     #[error(transparent)]
     MsgPackEncode(#[from] rmp_serde::encode::Error),
 
+    #[error(transparent)]
+    MsgPackDecode(#[from] rmp_serde::decode::Error),
+
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+
+    #[error(transparent)]
+    Arrow(#[from] arrow::error::ArrowError),
+
+    #[error(transparent)]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    #[error(transparent)]
+    Xlsx(#[from] rust_xlsxwriter::XlsxError),
+
+    #[error(
+        "Invalid config file {}: {}",
+        .path.display(),
+        .errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    Config {
+        path: std::path::PathBuf,
+        errors: Vec<crate::config::FieldError>,
+    },
+
+    /// 系统没有应用配置目录，需通过参数或环境变量指定配置文件
+    #[error("Cannot determine the config directory, set INSIGHT_CONFIG to a config file")]
+    ConfigDir,
+
+    #[error("Database profile not found: {0}")]
+    ProfileNotFound(String),
+
+    #[error("Database profile already exists: {0}")]
+    ProfileExists(String),
+
+    #[error("Cannot remove the active database profile: {0}")]
+    ActiveProfile(String),
+
+    #[error(transparent)]
+    Keyring(#[from] keyring::Error),
+
+    #[error("Secret storage error: {0}")]
+    Secret(String),
+
+    #[error("Unsupported export format: {0}")]
+    ExportFormat(String),
+
+    #[error("Archive format error: {0}")]
+    ArchiveFormat(String),
+
     #[error("Log format error: {0}")]
     LogFormat(String),
 
+    /// 日志中某一行格式错误，`line` 从 1 开始
+    #[error("Log format error at line {line}: {reason}")]
+    LogLine { line: usize, reason: String },
+
+    /// 任务已开始彻底删除，不能再恢复
+    #[error("Job {0} is being purged and cannot be restored")]
+    JobPurging(i64),
+
+    #[error("Invalid page cursor: {0}")]
+    Cursor(String),
+
+    #[error("Missing or invalid API token")]
+    Unauthorized,
+
+    /// 本地 HTTP 接口拒绝非本机的 `Host` 或 `Origin`
+    #[error("Request from a foreign origin: {0}")]
+    ForbiddenOrigin(String),
+
     // #[error("{0}")]
     // Custom(String),
 }
 
+/// 错误的大类，便于前端按类别展示或处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    /// 文件读写
+    Io,
+    /// 数据库连接与查询
+    Database,
+    /// 配置文件
+    Config,
+    /// 凭据存储
+    Secret,
+    /// 日志、归档等输入格式
+    Format,
+    /// 导出文件
+    Export,
+    /// 本地 HTTP 接口
+    Server,
+    /// 任务的导入与管理
+    Job,
+    Internal,
+}
+
+/// 同一调度系统的作业号不能重复导入，见 `sql/up.sql`
+const JOB_UNIQUE_CONSTRAINT: &str = "job_info_scheduler_id_unique";
+
+impl Error {
+    /// 稳定的错误码，形如 `类别.原因`，不随提示文字或语言变化
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Io(e) => match e.kind() {
+                std::io::ErrorKind::NotFound => "io.not_found",
+                std::io::ErrorKind::PermissionDenied => "io.permission_denied",
+                _ => "io.other",
+            },
+            Error::SerdeJson(_) => "format.json",
+            Error::Sqlx(e) => match e {
+                sqlx::Error::RowNotFound => "database.not_found",
+                sqlx::Error::Database(e) if e.constraint() == Some(JOB_UNIQUE_CONSTRAINT) => {
+                    "job.duplicate"
+                }
+                sqlx::Error::Database(e) if e.is_unique_violation() => "database.unique_violation",
+                sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
+                    "database.foreign_key_violation"
+                }
+                sqlx::Error::Database(_) => "database.query",
+                sqlx::Error::Configuration(_) => "database.configuration",
+                sqlx::Error::Io(_)
+                | sqlx::Error::Tls(_)
+                | sqlx::Error::PoolTimedOut
+                | sqlx::Error::PoolClosed => "database.connection",
+                _ => "database.other",
+            },
+            #[cfg(feature = "desktop")]
+            Error::Tauri(_) => "internal.tauri",
+            Error::MsgPackEncode(_) | Error::MsgPackDecode(_) => "format.msgpack",
+            Error::Csv(_) => "format.csv",
+            Error::Arrow(_) => "export.arrow",
+            Error::Parquet(_) => "export.parquet",
+            Error::Xlsx(_) => "export.xlsx",
+            Error::Config { .. } => "config.invalid",
+            Error::ConfigDir => "config.no_dir",
+            Error::ProfileNotFound(_) => "config.profile_not_found",
+            Error::ProfileExists(_) => "config.profile_exists",
+            Error::ActiveProfile(_) => "config.profile_active",
+            Error::Keyring(_) => "secret.keyring",
+            Error::Secret(_) => "secret.invalid",
+            Error::ExportFormat(_) => "export.unsupported_format",
+            Error::ArchiveFormat(_) => "format.archive",
+            Error::LogFormat(_) => "format.log",
+            Error::LogLine { .. } => "format.log_line",
+            Error::Cursor(_) => "format.cursor",
+            Error::JobPurging(_) => "job.purging",
+            Error::Unauthorized => "server.unauthorized",
+            Error::ForbiddenOrigin(_) => "server.forbidden_origin",
+        }
+    }
+
+    pub fn category(&self) -> Category {
+        match self.code().split_once('.').map_or("", |(category, _)| category) {
+            "io" => Category::Io,
+            "database" => Category::Database,
+            "config" => Category::Config,
+            "secret" => Category::Secret,
+            "format" => Category::Format,
+            "export" => Category::Export,
+            "server" => Category::Server,
+            "job" => Category::Job,
+            _ => Category::Internal,
+        }
+    }
+
+    /// 与错误相关的附加信息，如 SQL 状态码、行号、出错的字段
+    ///
+    /// `source` 为底层错误的原始信息，未翻译。
+    pub fn details(&self) -> Map<String, Value> {
+        let mut details = Map::new();
+        match self {
+            Error::Io(e) => {
+                details.insert("kind".into(), e.kind().to_string().into());
+            }
+            Error::SerdeJson(e) => {
+                details.insert("line".into(), e.line().into());
+                details.insert("column".into(), e.column().into());
+            }
+            Error::Sqlx(sqlx::Error::Database(e)) => {
+                if let Some(code) = e.code() {
+                    details.insert("sql_state".into(), code.into());
+                }
+                if let Some(constraint) = e.constraint() {
+                    details.insert("constraint".into(), constraint.into());
+                }
+                if let Some(table) = e.table() {
+                    details.insert("table".into(), table.into());
+                }
+            }
+            Error::Csv(e) => {
+                if let Some(position) = e.position() {
+                    details.insert("line".into(), position.line().into());
+                }
+            }
+            Error::Config { path, errors } => {
+                details.insert("path".into(), path.display().to_string().into());
+                details.insert("errors".into(), serde_json::json!(errors));
+            }
+            Error::ProfileNotFound(name) | Error::ProfileExists(name) | Error::ActiveProfile(name) => {
+                details.insert("profile".into(), name.as_str().into());
+            }
+            Error::ExportFormat(path) => {
+                details.insert("path".into(), path.as_str().into());
+            }
+            Error::JobPurging(job_id) => {
+                details.insert("job_id".into(), (*job_id).into());
+            }
+            Error::ForbiddenOrigin(origin) => {
+                details.insert("origin".into(), origin.as_str().into());
+            }
+            Error::Secret(reason)
+            | Error::ArchiveFormat(reason)
+            | Error::LogFormat(reason)
+            | Error::Cursor(reason) => {
+                details.insert("reason".into(), reason.as_str().into());
+            }
+            Error::LogLine { line, reason } => {
+                details.insert("line".into(), (*line).into());
+                details.insert("reason".into(), reason.as_str().into());
+            }
+            _ => {}
+        }
+        let source = match self {
+            Error::Io(e) => Some(e.to_string()),
+            Error::Sqlx(sqlx::Error::Database(e)) => Some(e.message().to_owned()),
+            Error::Sqlx(e) => Some(e.to_string()),
+            Error::SerdeJson(_)
+            | Error::Csv(_)
+            | Error::Arrow(_)
+            | Error::Parquet(_)
+            | Error::Xlsx(_)
+            | Error::MsgPackEncode(_)
+            | Error::MsgPackDecode(_)
+            | Error::Keyring(_) => Some(self.to_string()),
+            #[cfg(feature = "desktop")]
+            Error::Tauri(e) => Some(e.to_string()),
+            _ => None,
+        };
+        if let Some(source) = source {
+            details.insert("source".into(), source.into());
+        }
+        details
+    }
+
+    /// 按语言翻译的提示信息，目录中没有时使用英文的 [`Display`](std::fmt::Display)
+    pub fn localized(&self, locale: Locale) -> String {
+        match catalog::template(self.code(), locale) {
+            Some(template) => catalog::render(template, &self.details()),
+            None => self.to_string(),
+        }
+    }
+}
+
+/// 序列化为 `{ code, category, message, details }`，`message` 使用 [`Locale::current`] 的语言
 impl serde::Serialize for Error {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::ser::Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        self.serialize_localized(Locale::current(), serializer)
+    }
+}
+
+impl Error {
+    /// 按指定语言序列化，不读取全局设置
+    fn serialize_localized<S>(&self, locale: Locale, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        let mut state = serializer.serialize_struct("Error", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("category", &self.category())?;
+        state.serialize_field("message", &self.localized(locale))?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FieldError;
+    use sqlx::error::{DatabaseError, ErrorKind};
+
+    /// 违反唯一约束的数据库错误
+    #[derive(Debug)]
+    struct UniqueViolation(&'static str);
+
+    impl std::fmt::Display for UniqueViolation {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "duplicate key value violates unique constraint \"{}\"", self.0)
+        }
+    }
+
+    impl std::error::Error for UniqueViolation {}
+
+    impl DatabaseError for UniqueViolation {
+        fn message(&self) -> &str {
+            "duplicate key value"
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn constraint(&self) -> Option<&str> {
+            Some(self.0)
+        }
+
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::UniqueViolation
+        }
+    }
+
+    #[test]
+    fn test_code_and_category() {
+        let err = Error::Io(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(err.code(), "io.not_found");
+        assert_eq!(err.category(), Category::Io);
+
+        let err = Error::Sqlx(sqlx::Error::RowNotFound);
+        assert_eq!(err.code(), "database.not_found");
+        assert_eq!(err.category(), Category::Database);
+
+        let err = Error::Sqlx(sqlx::Error::Database(Box::new(UniqueViolation(
+            JOB_UNIQUE_CONSTRAINT,
+        ))));
+        assert_eq!(err.code(), "job.duplicate");
+        assert_eq!(err.category(), Category::Job);
+        let err = Error::Sqlx(sqlx::Error::Database(Box::new(UniqueViolation("job_tags_pkey"))));
+        assert_eq!(err.code(), "database.unique_violation");
+
+        let err = Error::JobPurging(7);
+        assert_eq!(err.code(), "job.purging");
+        assert_eq!(err.localized(Locale::ZhCn), "任务 7 正在彻底删除，不能恢复");
+
+        let err = serde_json::from_str::<Value>("{\n  \"a\": }").unwrap_err();
+        let details = Error::from(err).details();
+        assert_eq!(details["line"], 2);
+        assert_eq!(details["column"], 8);
+    }
+
+    #[test]
+    fn test_localized() {
+        let err = Error::Config {
+            path: "config.json".into(),
+            errors: vec![
+                FieldError {
+                    field: "active".to_owned(),
+                    message: "profile 'a' not found".to_owned(),
+                },
+                FieldError {
+                    field: "server.port".to_owned(),
+                    message: "must not be 0".to_owned(),
+                },
+            ],
+        };
+        assert_eq!(
+            err.localized(Locale::ZhCn),
+            "配置文件 config.json 有误：active: profile 'a' not found; server.port: must not be 0"
+        );
+        assert_eq!(err.localized(Locale::EnUs), err.to_string());
+
+        // 不修改全局语言，避免影响并行运行的其它测试
+        let value = Error::ProfileNotFound("sandbox".to_owned())
+            .serialize_localized(Locale::ZhCn, serde_json::value::Serializer)
+            .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "code": "config.profile_not_found",
+                "category": "config",
+                "message": "数据库配置不存在：sandbox",
+                "details": { "profile": "sandbox" },
+            })
+        );
+        assert_eq!(Locale::from_tag("zh_TW.UTF-8"), Locale::ZhCn);
+        assert_eq!(Locale::from_tag("en"), Locale::EnUs);
     }
 }
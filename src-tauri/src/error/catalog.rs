@@ -0,0 +1,177 @@
+//! 错误提示的中英文目录
+//!
+//! 模板中的 `{name}` 以 [`Error::details`](super::Error::details) 中的同名字段替换。
+
+use serde_json::{Map, Value};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 提示信息的语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Locale {
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+    #[serde(rename = "en-US")]
+    EnUs,
+}
+
+/// 当前语言，0 表示尚未设置，按环境变量推断
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+impl Locale {
+    /// 由 BCP 47 语言标签（如 `zh-CN`、`zh_TW.UTF-8`、`en`）推断，非中文均为英文
+    pub fn from_tag(tag: &str) -> Self {
+        if tag.to_ascii_lowercase().starts_with("zh") {
+            Self::ZhCn
+        } else {
+            Self::EnUs
+        }
+    }
+
+    /// 当前语言，未通过 [`set`](Self::set) 设置时按 `LC_ALL`、`LANG` 推断
+    pub fn current() -> Self {
+        match CURRENT.load(Ordering::Relaxed) {
+            1 => Self::ZhCn,
+            2 => Self::EnUs,
+            _ => ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .find_map(|name| std::env::var(name).ok().filter(|v| !v.is_empty()))
+                .map_or(Self::EnUs, |tag| Self::from_tag(&tag)),
+        }
+    }
+
+    pub fn set(locale: Self) {
+        let value = match locale {
+            Self::ZhCn => 1,
+            Self::EnUs => 2,
+        };
+        CURRENT.store(value, Ordering::Relaxed);
+    }
+}
+
+/// 错误码对应的提示模板
+pub(super) fn template(code: &str, locale: Locale) -> Option<&'static str> {
+    let (zh_cn, en_us) = match code {
+        "io.not_found" => ("文件不存在：{source}", "File not found: {source}"),
+        "io.permission_denied" => ("没有访问权限：{source}", "Permission denied: {source}"),
+        "io.other" => ("文件读写失败：{source}", "I/O error: {source}"),
+        "format.json" => (
+            "JSON 格式错误（第 {line} 行第 {column} 列）：{source}",
+            "Invalid JSON at line {line}, column {column}: {source}",
+        ),
+        "format.msgpack" => (
+            "MessagePack 编解码失败：{source}",
+            "MessagePack error: {source}",
+        ),
+        "format.csv" => ("CSV 格式错误：{source}", "Invalid CSV: {source}"),
+        "format.archive" => ("归档文件格式错误：{reason}", "Invalid archive: {reason}"),
+        "format.log" => ("日志格式错误：{reason}", "Invalid log: {reason}"),
+        "format.log_line" => (
+            "日志第 {line} 行格式错误：{reason}",
+            "Invalid log at line {line}: {reason}",
+        ),
+        "format.cursor" => ("分页游标无效：{reason}", "Invalid page cursor: {reason}"),
+        "database.not_found" => ("数据库中没有找到记录", "Record not found in database"),
+        "database.unique_violation" => (
+            "数据已存在（{constraint}）",
+            "Record already exists ({constraint})",
+        ),
+        "job.duplicate" => (
+            "该作业已导入，同一调度系统的作业号不能重复",
+            "This job has already been imported, scheduler job ids must be unique",
+        ),
+        "job.purging" => (
+            "任务 {job_id} 正在彻底删除，不能恢复",
+            "Job {job_id} is being purged and cannot be restored",
+        ),
+        "database.foreign_key_violation" => (
+            "数据被引用或引用的数据不存在（{constraint}）",
+            "Foreign key violation ({constraint})",
+        ),
+        "database.query" => (
+            "数据库查询失败（SQLSTATE {sql_state}）：{source}",
+            "Database query failed (SQLSTATE {sql_state}): {source}",
+        ),
+        "database.configuration" => (
+            "数据库连接配置有误：{source}",
+            "Invalid database settings: {source}",
+        ),
+        "database.connection" => (
+            "无法连接数据库：{source}",
+            "Cannot connect to database: {source}",
+        ),
+        "database.other" => ("数据库错误：{source}", "Database error: {source}"),
+        "config.invalid" => (
+            "配置文件 {path} 有误：{errors}",
+            "Invalid config file {path}: {errors}",
+        ),
+        "config.no_dir" => (
+            "无法确定应用配置目录，请通过环境变量 INSIGHT_CONFIG 指定配置文件",
+            "Cannot determine the config directory, set INSIGHT_CONFIG to a config file",
+        ),
+        "config.profile_not_found" => (
+            "数据库配置不存在：{profile}",
+            "Database profile not found: {profile}",
+        ),
+        "config.profile_exists" => (
+            "数据库配置已存在：{profile}",
+            "Database profile already exists: {profile}",
+        ),
+        "config.profile_active" => (
+            "不能删除正在使用的数据库配置：{profile}",
+            "Cannot remove the active database profile: {profile}",
+        ),
+        "secret.keyring" => ("系统凭据管理出错：{source}", "Keyring error: {source}"),
+        "secret.invalid" => ("凭据存储出错：{reason}", "Secret storage error: {reason}"),
+        "export.unsupported_format" => (
+            "不支持的导出格式：{path}",
+            "Unsupported export format: {path}",
+        ),
+        "export.arrow" | "export.parquet" | "export.xlsx" => {
+            ("导出失败：{source}", "Export failed: {source}")
+        }
+        "server.unauthorized" => (
+            "缺少访问令牌或令牌无效",
+            "Missing or invalid API token",
+        ),
+        "server.forbidden_origin" => (
+            "拒绝来自非本机的请求：{origin}",
+            "Request from a foreign origin: {origin}",
+        ),
+        "internal.tauri" => ("内部错误：{source}", "Internal error: {source}"),
+        _ => return None,
+    };
+    Some(match locale {
+        Locale::ZhCn => zh_cn,
+        Locale::EnUs => en_us,
+    })
+}
+
+/// 以 `details` 填充模板，缺少的字段替换为空
+pub(super) fn render(template: &str, details: &Map<String, Value>) -> String {
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        message.push_str(&rest[..start]);
+        let name = &rest[start + 1..start + end];
+        if let Some(value) = details.get(name) {
+            message.push_str(&to_text(value));
+        }
+        rest = &rest[start + end + 1..];
+    }
+    message.push_str(rest);
+    message
+}
+
+/// 字符串不带引号，数组以 `; ` 连接，对象的值以 `: ` 连接（如字段错误）
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(to_text).collect::<Vec<_>>().join("; "),
+        Value::Object(map) => map.values().map(to_text).collect::<Vec<_>>().join(": "),
+        value => value.to_string(),
+    }
+}
@@ -1,22 +1,56 @@
+#[cfg(feature = "cli")]
+pub mod cli;
 mod commands;
 mod config;
 mod error;
+mod secret;
+#[cfg(feature = "server")]
+mod server;
 
-use commands::Cache;
-use config::AppConfig;
-use sqlx::PgPool;
-use tauri::async_runtime;
-use tokio::sync::RwLock;
+#[cfg(feature = "desktop")]
+use {
+    commands::{Cache, ConfigLock},
+    config::AppConfig,
+    tauri::async_runtime,
+    tokio::sync::RwLock,
+};
 
-type Result<T> = std::result::Result<T, error::Error>;
+pub type Result<T> = std::result::Result<T, error::Error>;
 
+/// 不依赖 Tauri 的解析与查询接口，供 Python 绑定等其他前端复用
+pub mod api {
+    pub use crate::commands::{
+        fetch_error_log, fetch_job, fetch_job_list, fetch_job_page, ErrorLogEntry, ErrorLogSummary,
+        IdleGap, JobFilter, JobHeader, JobInfo, JobPage, JobPageRequest, JobSortKey, LogColumns,
+        LogParser, ParsedLog, SortOrder, TimingReport,
+    };
+    pub use crate::config::{AppConfig, DatabaseConfig};
+    pub use crate::error::{Category, Error, Locale};
+    pub use crate::Result;
+    pub use sqlx::PgPool;
+}
+
+#[cfg(feature = "desktop")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let config = AppConfig::load().expect("Failed to load config");
+    // `--config <FILE>` 指定配置文件
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            if let Some(path) = args.next() {
+                AppConfig::set_path(path);
+            }
+        }
+    }
+    let config = AppConfig::load().unwrap_or_else(|e| panic!("Failed to load config: {e}"));
+    let database = config
+        .database()
+        .unwrap_or_else(|e| panic!("Failed to read database config: {e}"));
     let pool = async_runtime::block_on(async {
-        PgPool::connect(&config.database.url())
+        database
+            .connect()
             .await
-            .expect("Failed to connect to database")
+            .unwrap_or_else(|e| panic!("Failed to connect to database: {e}"))
     });
 
     tauri::Builder::default()
@@ -25,16 +59,65 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .manage(RwLock::new(pool))
         .manage(RwLock::new(Cache::new()))
+        .manage(ConfigLock::default())
+        .setup(move |_app| {
+            #[cfg(feature = "server")]
+            if config.server.enabled {
+                let app = _app.handle().clone();
+                let port = config.server.port;
+                async_runtime::spawn(async move {
+                    if let Err(error) = server::serve(app.clone(), port).await {
+                        commands::BackgroundError {
+                            task: "server",
+                            job_id: None,
+                            error: &error,
+                        }
+                        .emit(&app);
+                    }
+                });
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::import_error_log,
             commands::read_config,
             commands::write_config,
+            commands::set_locale,
+            commands::add_profile,
+            commands::edit_profile,
+            commands::remove_profile,
+            commands::test_profile,
+            commands::activate_profile,
+            commands::test_connection,
             commands::get_total_time,
+            commands::get_timing_report,
             commands::get_job_list,
+            commands::get_job_page,
+            commands::get_tags,
+            commands::set_job_tags,
+            commands::set_job_starred,
+            commands::get_job_note,
+            commands::set_job_note,
             commands::get_error_log,
             commands::clear_error_log_cache,
             commands::find_job,
             commands::remove_job,
+            commands::restore_job,
+            commands::purge_jobs,
+            commands::export_job,
+            commands::export_archive,
+            commands::import_archive,
+            commands::get_modeling_list,
+            commands::create_modeling,
+            commands::rename_modeling,
+            commands::describe_modeling,
+            commands::remove_modeling,
+            commands::attach_jobs,
+            commands::detach_jobs,
+            commands::get_modeling_jobs,
+            commands::get_modeling_statistics,
+            commands::export_modeling_statistics,
+            commands::get_scaling_analysis,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,316 @@
+//! 数据库密码等凭据的安全存储
+//!
+//! 优先使用操作系统的凭据管理：Linux 上为通过 D-Bus 访问的 Secret Service，
+//! macOS 为钥匙串，Windows 为凭据管理器。凭据管理不可用时（如没有桌面会话的计算节点），
+//! 回退到以每次安装随机生成的密钥加密的文件，见 [`EncryptedFile`]。
+//! 密钥与加密文件放在不同的目录，单独复制或同步配置目录（如 Windows 的漫游配置、备份的 dotfiles）
+//! 不会泄露密码；能读取用户全部文件的程序仍可解密，此时应使用系统凭据管理。
+
+use crate::error::Error;
+use crate::Result;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+pub(crate) struct SecretStore {
+    file: EncryptedFile,
+}
+
+impl SecretStore {
+    /// 在系统凭据管理中使用的服务名
+    const SERVICE: &'static str = "insight";
+    pub(crate) const KEY_FILE: &'static str = EncryptedFile::KEY_FILE;
+    pub(crate) const FILENAME: &'static str = EncryptedFile::FILENAME;
+
+    /// `dir` 为回退时加密文件所在的目录，`key_dir` 为密钥所在的目录
+    pub(crate) fn new(dir: &Path, key_dir: &Path) -> Self {
+        Self {
+            file: EncryptedFile::new(dir, key_dir),
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Result<Option<String>> {
+        let entry = keyring::Entry::new(Self::SERVICE, name);
+        match entry.and_then(|entry| entry.get_password()) {
+            Ok(secret) => Ok(Some(secret)),
+            // 系统凭据管理中没有时，可能是之前回退保存在了加密文件中
+            Err(keyring::Error::NoEntry) => self.file.get(name),
+            Err(e) if Self::is_unavailable(&e) => self.file.get(name),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub(crate) fn set(&self, name: &str, secret: &str) -> Result<()> {
+        let entry = keyring::Entry::new(Self::SERVICE, name);
+        match entry.and_then(|entry| entry.set_password(secret)) {
+            // 清除之前回退保存的旧凭据
+            Ok(()) => self.file.remove(name),
+            Err(e) if Self::is_unavailable(&e) => self.file.set(name, secret),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 同时从系统凭据管理与加密文件中删除
+    #[cfg_attr(not(feature = "desktop"), allow(dead_code))]
+    pub(crate) fn remove(&self, name: &str) -> Result<()> {
+        let entry = keyring::Entry::new(Self::SERVICE, name);
+        match entry.and_then(|entry| entry.delete_credential()) {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) if Self::is_unavailable(&e) => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.file.remove(name)
+    }
+
+    /// 系统凭据管理不可用，例如没有运行 Secret Service 或当前会话无法访问
+    fn is_unavailable(e: &keyring::Error) -> bool {
+        matches!(
+            e,
+            keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_)
+        )
+    }
+}
+
+/// 加密保存凭据的文件
+///
+/// 密钥为首次保存凭据时随机生成的 [`KEY_FILE`](Self::KEY_FILE)，与凭据文件 [`FILENAME`](Self::FILENAME)
+/// 保存在不同的目录，且在 Unix 上仅当前用户可读写。旧版本放在凭据文件旁的密钥在首次使用时移走。凭据文件为 JSON 对象，
+/// 值为 base64 编码的 `nonce || ciphertext`（XChaCha20-Poly1305）。
+struct EncryptedFile {
+    key_path: PathBuf,
+    /// 旧版本的密钥位置，与凭据文件在同一目录
+    legacy_key_path: PathBuf,
+    path: PathBuf,
+}
+
+impl EncryptedFile {
+    const KEY_FILE: &'static str = "secret.key";
+    const FILENAME: &'static str = "secrets.json";
+    const NONCE_SIZE: usize = 24;
+
+    fn new(dir: &Path, key_dir: &Path) -> Self {
+        Self {
+            key_path: key_dir.join(Self::KEY_FILE),
+            legacy_key_path: dir.join(Self::KEY_FILE),
+            path: dir.join(Self::FILENAME),
+        }
+    }
+
+    /// 读取密钥，不存在时移入旧版本的密钥
+    ///
+    /// 仍不存在时，`create` 为 `true` 则生成新的密钥，否则返回错误：
+    /// 此时已有的凭据无法解密，生成新密钥只会使其永久丢失。
+    fn cipher(&self, create: bool) -> Result<XChaCha20Poly1305> {
+        let key = match read_optional(&self.key_path)? {
+            Some(key) => key,
+            None => match read_optional(&self.legacy_key_path)? {
+                Some(legacy) => {
+                    let key = self.create_key(legacy)?;
+                    match fs::remove_file(&self.legacy_key_path) {
+                        Ok(()) => {}
+                        // 同时使用的另一个进程已经移走
+                        Err(e) if e.kind() == ErrorKind::NotFound => {}
+                        Err(e) => return Err(e.into()),
+                    }
+                    key
+                }
+                None if create => {
+                    self.create_key(XChaCha20Poly1305::generate_key(&mut OsRng).to_vec())?
+                }
+                None => {
+                    return Err(Error::Secret(format!(
+                        "missing key file {}",
+                        self.key_path.display()
+                    )))
+                }
+            },
+        };
+        XChaCha20Poly1305::new_from_slice(&key)
+            .map_err(|_| Error::Secret(format!("invalid key file {}", self.key_path.display())))
+    }
+
+    /// 以 `key` 创建密钥文件，已存在时（如同时首次使用）不覆盖，返回已有的密钥
+    fn create_key(&self, key: Vec<u8>) -> Result<Vec<u8>> {
+        if let Some(dir) = self.key_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&self.key_path) {
+            Ok(mut file) => {
+                file.write_all(&key)?;
+                Ok(key)
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(fs::read(&self.key_path)?),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn read(&self) -> Result<BTreeMap<String, String>> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        write_private(
+            &self.path,
+            serde_json::to_string_pretty(secrets)?.as_bytes(),
+        )
+    }
+
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        let Some(encoded) = self.read()?.remove(name) else {
+            return Ok(None);
+        };
+        let invalid = || Error::Secret(format!("cannot decrypt secret '{name}'"));
+        let data = BASE64.decode(encoded).map_err(|_| invalid())?;
+        if data.len() < Self::NONCE_SIZE {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = data.split_at(Self::NONCE_SIZE);
+        let secret = self
+            .cipher(false)?
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())?;
+        String::from_utf8(secret).map(Some).map_err(|_| invalid())
+    }
+
+    fn set(&self, name: &str, secret: &str) -> Result<()> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher(true)?
+            .encrypt(&nonce, secret.as_bytes())
+            .map_err(|_| Error::Secret(format!("cannot encrypt secret '{name}'")))?;
+
+        let mut data = nonce.to_vec();
+        data.extend(ciphertext);
+        let mut secrets = self.read()?;
+        secrets.insert(name.to_owned(), BASE64.encode(data));
+        self.write(&secrets)
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        let mut secrets = self.read()?;
+        if secrets.remove(name).is_some() {
+            self.write(&secrets)?;
+        }
+        Ok(())
+    }
+}
+
+/// 读取文件，不存在时返回 `None`
+fn read_optional(path: &Path) -> Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 写入文件，在 Unix 上仅当前用户可读写
+///
+/// 创建时的权限受 umask 影响且不会修改已有文件，因此在写入内容前重新设置。
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("insight-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_encrypted_file() {
+        let dir = temp_dir("secrets");
+        let file = EncryptedFile::new(&dir, &dir.join("key"));
+        assert_eq!(file.get("db").unwrap(), None);
+
+        file.set("db", "p@ss:word/").unwrap();
+        file.set("other", "secret").unwrap();
+        assert_eq!(file.get("db").unwrap().as_deref(), Some("p@ss:word/"));
+
+        // 文件中不应出现明文
+        let content = fs::read_to_string(dir.join(EncryptedFile::FILENAME)).unwrap();
+        assert!(!content.contains("p@ss:word/"));
+
+        // 换用其它密钥时无法解密
+        fs::write(dir.join("key").join(EncryptedFile::KEY_FILE), [0u8; 32]).unwrap();
+        assert!(matches!(file.get("db"), Err(Error::Secret(_))));
+
+        file.remove("db").unwrap();
+        assert_eq!(file.get("db").unwrap(), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_key() {
+        let dir = temp_dir("missing-key");
+        let file = EncryptedFile::new(&dir, &dir.join("key"));
+        file.set("db", "secret").unwrap();
+        let key_path = dir.join("key").join(EncryptedFile::KEY_FILE);
+        let key = fs::read(&key_path).unwrap();
+
+        // 密钥丢失时读取报错，且不生成新的密钥
+        fs::remove_file(&key_path).unwrap();
+        assert!(matches!(file.get("db"), Err(Error::Secret(_))));
+        assert!(!key_path.exists());
+
+        // 已有密钥时不覆盖
+        fs::write(&key_path, &key).unwrap();
+        assert_eq!(file.create_key(vec![0; 32]).unwrap(), key);
+        assert_eq!(file.get("db").unwrap().as_deref(), Some("secret"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_legacy_key() {
+        let dir = temp_dir("legacy-key");
+        let legacy = EncryptedFile::new(&dir, &dir);
+        legacy.set("db", "secret").unwrap();
+
+        // 密钥移到新目录，仍能解密旧的凭据
+        let file = EncryptedFile::new(&dir, &dir.join("key"));
+        assert_eq!(file.get("db").unwrap().as_deref(), Some("secret"));
+        assert!(!dir.join(EncryptedFile::KEY_FILE).exists());
+        assert!(dir.join("key").join(EncryptedFile::KEY_FILE).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private_existing() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("private");
+        let path = dir.join("token");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        write_private(&path, b"new").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,440 @@
+//! 本地 HTTP 接口，供看板与 Jupyter Notebook 读取与桌面应用相同的数据
+//!
+//! 与 Tauri 命令共享 [`PgPool`] 状态，仅监听 `127.0.0.1`。
+//! 根据请求的 `Accept` 头返回 JSON（默认）或 MessagePack（`application/msgpack`）；
+//! 任务列表与误差日志还可以返回 Arrow IPC 流（`application/vnd.apache.arrow.stream`），
+//! 边查询边发送，其它接口仍返回 JSON。
+//!
+//! 所有请求须带 `Authorization: Bearer <token>`，令牌为首次启动时随机生成的 [`TOKEN_FILE`]，
+//! 与配置文件在同一目录且仅当前用户可读。`Host` 与 `Origin`（若有）须为本机地址，
+//! 避免网页通过跨站请求或 DNS 重绑定访问接口。
+//!
+//! | 方法 | 路径 | 对应命令 |
+//! | ---- | ---- | -------- |
+//! | `GET` | `/api/jobs` | `get_job_list`，可选参数 `tag`、`note`、`starred`、`search`、`deleted` |
+//! | `GET` | `/api/jobs/page` | `get_job_page`，另有参数 `sort`、`order`、`cursor`、`limit`、`offset` |
+//! | `POST` | `/api/jobs` | `import_error_log`，请求体为日志文件内容 |
+//! | `GET` | `/api/jobs/{job_id}` | `find_job` |
+//! | `GET` | `/api/jobs/{job_id}/error-log` | `get_error_log`，Arrow 格式只包含 `entries` |
+//! | `GET` | `/api/jobs/{job_id}/total-time` | `get_total_time` |
+
+use crate::commands::{
+    decode_error_log_message, error_log_messages, fetch_job, fetch_job_list, fetch_job_page,
+    fetch_total_time, import_log, job_list_stmt, ErrorLogEntry, IpcStream, JobFilter, JobPageRequest, RowBatchBuilder,
+    ARROW_BATCH_ROWS,
+};
+use crate::config::AppConfig;
+use crate::error::Error;
+use crate::secret::write_private;
+use crate::Result;
+use axum::body::Body;
+use axum::extract::{DefaultBodyLimit, FromRequestParts, Path, Query, Request, State};
+use axum::http::{header, request::Parts, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64;
+use base64::Engine;
+use futures_util::TryStreamExt;
+use sqlx::postgres::PgArguments;
+use sqlx::{Executor, PgPool};
+use std::convert::Infallible;
+use std::future::ready;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::ops::Deref;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{mpsc, RwLock};
+
+const JSON: &str = "application/json";
+const MSGPACK: &str = "application/msgpack";
+const ARROW: &str = "application/vnd.apache.arrow.stream";
+
+/// 访问令牌文件名，与配置文件在同一目录
+pub(crate) const TOKEN_FILE: &str = "server.token";
+
+/// 请求体的大小上限，日志文件通常远大于默认的 2 MB
+const MAX_BODY_SIZE: usize = 512 * 1024 * 1024;
+
+/// 启动 HTTP 服务，直到监听出错才返回
+pub(crate) async fn serve(app: AppHandle, port: u16) -> Result<()> {
+    let token = load_token()?;
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router(app, token)).await?;
+    Ok(())
+}
+
+/// 读取访问令牌，不存在时生成
+fn load_token() -> Result<Arc<str>> {
+    let path = AppConfig::dir()?.join(TOKEN_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(token) if !token.trim().is_empty() => Ok(token.trim().into()),
+        Ok(_) => create_token(&path),
+        Err(e) if e.kind() == ErrorKind::NotFound => create_token(&path),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn create_token(path: &std::path::Path) -> Result<Arc<str>> {
+    let token = BASE64.encode(rand::random::<[u8; 32]>());
+    write_private(path, token.as_bytes())?;
+    Ok(token.into())
+}
+
+fn router(app: AppHandle, token: Arc<str>) -> Router {
+    Router::new()
+        .route("/api/jobs", get(job_list).post(import))
+        .route("/api/jobs/page", get(job_page))
+        .route("/api/jobs/{job_id}", get(job))
+        .route("/api/jobs/{job_id}/error-log", get(error_log))
+        .route("/api/jobs/{job_id}/total-time", get(total_time))
+        .layer(DefaultBodyLimit::max(MAX_BODY_SIZE))
+        .layer(middleware::from_fn_with_state(token, guard))
+        .with_state(app)
+}
+
+/// 校验请求来源与访问令牌，先于请求体的读取
+async fn guard(State(token): State<Arc<str>>, request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    if let Some(origin) = foreign_origin(headers) {
+        return Error::ForbiddenOrigin(origin).into_response();
+    }
+    if !authorized(headers, &token) {
+        return Error::Unauthorized.into_response();
+    }
+    next.run(request).await
+}
+
+/// 本机地址，不含端口
+fn is_loopback(host: &str) -> bool {
+    matches!(host, "127.0.0.1" | "localhost" | "[::1]")
+}
+
+/// `Host` 不是本机地址，或 `Origin` 存在且不是本机页面时，返回该值
+fn foreign_origin(headers: &HeaderMap) -> Option<String> {
+    let host = headers
+        .get(header::HOST)
+        .map(|value| value.to_str().unwrap_or_default());
+    // 去掉端口，IPv6 地址带方括号
+    let host_only = |host: &str| match host.rsplit_once(':') {
+        Some((name, port)) if !port.contains(']') => name.to_owned(),
+        _ => host.to_owned(),
+    };
+    match host {
+        Some(host) if is_loopback(&host_only(host)) => {}
+        Some(host) => return Some(host.to_owned()),
+        None => return Some(String::new()),
+    }
+
+    let origin = headers.get(header::ORIGIN)?;
+    let origin = origin.to_str().unwrap_or_default();
+    let local = url::Url::parse(origin).is_ok_and(|url| {
+        matches!(url.scheme(), "http" | "https")
+            && url.host_str().is_some_and(is_loopback)
+    });
+    (!local).then(|| origin.to_owned())
+}
+
+/// `Authorization: Bearer <token>` 与令牌一致，逐字节比较耗时与内容无关
+fn authorized(headers: &HeaderMap, token: &str) -> bool {
+    let Some(provided) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    provided.len() == token.len()
+        && provided
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn job_list(
+    State(app): State<AppHandle>,
+    Query(filter): Query<JobFilter>,
+    format: Format,
+) -> Result<Response> {
+    let pool = app.state::<RwLock<PgPool>>();
+    let pool = pool.read().await.clone();
+    if format == Format::Arrow {
+        let (stmt, arguments) = (job_list_stmt(&filter), filter.arguments()?);
+        return Ok(arrow_response(|tx| send_rows(pool, stmt, arguments, tx)));
+    }
+    let jobs = fetch_job_list(&pool, &filter).await?;
+    format.encode(&jobs)
+}
+
+async fn job_page(
+    State(app): State<AppHandle>,
+    Query(filter): Query<JobFilter>,
+    Query(page): Query<JobPageRequest>,
+    format: Format,
+) -> Result<Response> {
+    let pool = app.state::<RwLock<PgPool>>();
+    let page = fetch_job_page(pool.read().await.deref(), &filter, &page).await?;
+    format.encode(&page)
+}
+
+async fn job(
+    State(app): State<AppHandle>,
+    Path(job_id): Path<i64>,
+    format: Format,
+) -> Result<Response> {
+    let pool = app.state::<RwLock<PgPool>>();
+    let job = fetch_job(pool.read().await.deref(), job_id).await?;
+    format.encode(&job)
+}
+
+/// 返回与 `get_error_log` 相同的 `[summary, entries]` 结构，与其它接口一样保留字段名；
+/// Arrow 格式只包含 `entries`，加载步汇总的数据量很小，以 JSON 获取即可。
+/// 与桌面应用共用误差日志的缓存。
+async fn error_log(
+    State(app): State<AppHandle>,
+    Path(job_id): Path<i64>,
+    format: Format,
+) -> Result<Response> {
+    if format == Format::Arrow {
+        return Ok(arrow_response(move |tx| send_entries(app, job_id, tx)));
+    }
+    let (mut summary, mut entries) = (Vec::new(), Vec::new());
+    error_log_messages(&app, job_id, |message| {
+        ready(decode_error_log_message(&message).map(|(s, e)| {
+            summary.extend(s);
+            entries.extend(e);
+        }))
+    })
+    .await?;
+    format.encode(&(summary, entries))
+}
+
+async fn total_time(
+    State(app): State<AppHandle>,
+    Path(job_id): Path<i64>,
+    format: Format,
+) -> Result<Response> {
+    let pool = app.state::<RwLock<PgPool>>();
+    let total = fetch_total_time(pool.read().await.deref(), job_id).await?;
+    format.encode(&total)
+}
+
+async fn import(State(app): State<AppHandle>, format: Format, content: String) -> Result<Response> {
+    let pool = app.state::<RwLock<PgPool>>();
+    let job_id = import_log(pool.read().await.deref(), &content).await?;
+    Ok((StatusCode::CREATED, format.encode(&job_id)?).into_response())
+}
+
+/// 在后台查询并编码为 Arrow IPC 流，边编码边作为响应体发送
+///
+/// 响应头发送后出错只能中断连接，客户端会读到不完整的流。
+fn arrow_response<F, Fut>(produce: F) -> Response
+where
+    F: FnOnce(mpsc::Sender<Result<Vec<u8>>>) -> Fut,
+    Fut: std::future::Future<Output = Result<()>> + Send + 'static,
+{
+    // 容量很小，客户端读取慢时查询随之暂停，内存占用不随结果增长
+    let (tx, rx) = mpsc::channel(2);
+    let task = produce(tx.clone());
+    tokio::spawn(async move {
+        if let Err(e) = task.await {
+            let _ = tx.send(Err(e)).await;
+        }
+    });
+    let chunks = futures_util::stream::unfold(rx, |mut rx| async {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+    ([(header::CONTENT_TYPE, ARROW)], Body::from_stream(chunks)).into_response()
+}
+
+/// 按查询结果的列类型构建 record batch，每 [`ARROW_BATCH_ROWS`] 行发送一次
+async fn send_rows(
+    pool: PgPool,
+    stmt: &'static str,
+    arguments: PgArguments,
+    tx: mpsc::Sender<Result<Vec<u8>>>,
+) -> Result<()> {
+    // 没有结果时也需要 schema，因此先取得列信息
+    let describe = pool.describe(stmt).await?;
+    let mut builder = RowBatchBuilder::new(describe.columns())?;
+    let mut stream = IpcStream::new(&builder.finish()?.schema())?;
+    let mut rows = sqlx::query_with(stmt, arguments).fetch(&pool);
+    while let Some(row) = rows.try_next().await? {
+        builder.append(&row)?;
+        if builder.len() >= ARROW_BATCH_ROWS {
+            stream.write(&builder.finish()?)?;
+            send(&tx, stream.take()).await?;
+        }
+    }
+    if builder.len() > 0 {
+        stream.write(&builder.finish()?)?;
+    }
+    send(&tx, stream.finish()?).await
+}
+
+/// 发送误差日志，列与 [`ErrorLogEntry::record_batch`] 相同，每条缓存消息一个 record batch
+async fn send_entries(
+    app: AppHandle,
+    job_id: i64,
+    tx: mpsc::Sender<Result<Vec<u8>>>,
+) -> Result<()> {
+    let batch = |entries: &[ErrorLogEntry]| {
+        ErrorLogEntry::record_batch(entries.iter().map(|entry| (job_id, entry)))
+    };
+    let mut stream = IpcStream::new(&batch(&[])?.schema())?;
+    error_log_messages(&app, job_id, |message| {
+        let bytes = decode_error_log_message(&message)
+            .and_then(|(_, entries)| batch(&entries))
+            .and_then(|batch| stream.write(&batch))
+            .map(|()| stream.take());
+        let tx = tx.clone();
+        async move { send(&tx, bytes?).await }
+    })
+    .await?;
+    send(&tx, stream.finish()?).await
+}
+
+/// 客户端断开后停止查询
+async fn send(tx: &mpsc::Sender<Result<Vec<u8>>>, bytes: Vec<u8>) -> Result<()> {
+    tx.send(Ok(bytes))
+        .await
+        .map_err(|_| Error::Io(ErrorKind::BrokenPipe.into()))
+}
+
+/// 响应格式，由 `Accept` 头协商
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    MsgPack,
+    /// 只用于任务列表与误差日志，其它接口按 JSON 编码
+    Arrow,
+}
+
+impl Format {
+    /// 只要 `Accept` 中列出 Arrow 或 MessagePack 即使用，忽略权重
+    fn negotiate(accept: &str) -> Self {
+        let media = accept
+            .split(',')
+            .filter_map(|range| range.split(';').next())
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        if media.contains(&ARROW) {
+            Self::Arrow
+        } else if media.iter().any(|media| matches!(*media, MSGPACK | "application/x-msgpack")) {
+            Self::MsgPack
+        } else {
+            Self::Json
+        }
+    }
+
+    /// 按格式编码，MessagePack 使用带字段名的编码
+    fn encode(self, value: &impl serde::Serialize) -> Result<Response> {
+        let response = match self {
+            Self::Json | Self::Arrow => ([(header::CONTENT_TYPE, JSON)], serde_json::to_vec(value)?),
+            Self::MsgPack => ([(header::CONTENT_TYPE, MSGPACK)], rmp_serde::to_vec_named(value)?),
+        };
+        Ok(response.into_response())
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Format {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        let accept = parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        Ok(Self::negotiate(accept))
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            Error::Sqlx(sqlx::Error::RowNotFound) => StatusCode::NOT_FOUND,
+            Error::Sqlx(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                StatusCode::CONFLICT
+            }
+            Error::LogFormat(_) | Error::LogLine { .. } | Error::Cursor(_) => StatusCode::BAD_REQUEST,
+            Error::Unauthorized => StatusCode::UNAUTHORIZED,
+            Error::ForbiddenOrigin(_) => StatusCode::FORBIDDEN,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = serde_json::json!({ "error": self }).to_string();
+        (status, [(header::CONTENT_TYPE, JSON)], body).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(Format::negotiate(""), Format::Json);
+        assert_eq!(Format::negotiate("*/*"), Format::Json);
+        assert_eq!(Format::negotiate("application/json"), Format::Json);
+        assert_eq!(Format::negotiate("application/msgpack"), Format::MsgPack);
+        assert_eq!(
+            Format::negotiate("application/json;q=0.5, application/x-msgpack;q=0.9"),
+            Format::MsgPack
+        );
+        assert_eq!(
+            Format::negotiate("application/msgpack, application/vnd.apache.arrow.stream"),
+            Format::Arrow
+        );
+    }
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.clone(), value.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_foreign_origin() {
+        assert_eq!(foreign_origin(&headers(&[(header::HOST, "127.0.0.1:7878")])), None);
+        assert_eq!(foreign_origin(&headers(&[(header::HOST, "[::1]:7878")])), None);
+        assert_eq!(
+            foreign_origin(&headers(&[
+                (header::HOST, "localhost:7878"),
+                (header::ORIGIN, "http://localhost:5173"),
+            ])),
+            None
+        );
+        // DNS 重绑定时 `Host` 为攻击者的域名
+        assert_eq!(
+            foreign_origin(&headers(&[(header::HOST, "evil.example:7878")])).as_deref(),
+            Some("evil.example:7878")
+        );
+        assert_eq!(
+            foreign_origin(&headers(&[
+                (header::HOST, "127.0.0.1:7878"),
+                (header::ORIGIN, "https://evil.example"),
+            ]))
+            .as_deref(),
+            Some("https://evil.example")
+        );
+        assert!(foreign_origin(&headers(&[])).is_some());
+    }
+
+    #[test]
+    fn test_authorized() {
+        let with = |value| headers(&[(header::AUTHORIZATION, value)]);
+        assert!(authorized(&with("Bearer abc"), "abc"));
+        assert!(!authorized(&with("Bearer abd"), "abc"));
+        assert!(!authorized(&with("Bearer ab"), "abc"));
+        assert!(!authorized(&with("abc"), "abc"));
+        assert!(!authorized(&headers(&[]), "abc"));
+    }
+}
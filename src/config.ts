@@ -1,15 +1,93 @@
 export interface DatabaseConfig {
+  /** 连接地址，与单独的字段同时设置时以非空的字段为准 */
+  url?: string;
   user: string;
+  /** 只用于提交新密码，后端不会返回已保存的密码，保存时为空表示不修改 */
   password: string;
+  /** 密码在凭据存储中的名称 */
+  password_secret?: string;
+  /** 凭据存储中是否已保存密码，由后端填入 */
+  has_password?: boolean;
   host: string;
-  port: number;
+  port?: number;
   database: string;
+  ssl_mode?: "disable" | "allow" | "prefer" | "require" | "verify-ca" | "verify-full";
+  ssl_root_cert?: string;
+  application_name?: string;
+  max_connections?: number;
+  min_connections?: number;
+  /** 秒 */
+  connect_timeout?: number;
+  /** 毫秒 */
+  statement_timeout?: number;
 
   [key: string] : any;
 }
 
+export interface ServerConfig {
+  enabled: boolean;
+  port: number;
+}
+
 export interface Config {
-  database: DatabaseConfig;
+  /** 配置文件格式版本，由后端维护 */
+  version?: number;
+  /** 当前使用的数据库配置名称 */
+  active: string;
+  /** 命名的数据库配置 */
+  profiles: Record<string, DatabaseConfig>;
+  server?: ServerConfig;
 
   [key: string] : any;
 }
+
+export interface TablePrivileges {
+  name: string;
+  exists: boolean;
+  select: boolean;
+  insert: boolean;
+  update: boolean;
+  delete: boolean;
+}
+
+/** `test_connection` 的结果 */
+export interface ConnectionReport {
+  server_version: string;
+  /** 毫秒 */
+  connect_time: number;
+  /** 毫秒 */
+  latency: number;
+  user: string;
+  database: string;
+  superuser: boolean;
+  can_create: boolean;
+  tables: TablePrivileges[];
+  missing_tables: string[];
+  pending_migrations: string[];
+}
+
+/** 后端命令返回的错误，`message` 已按 `set_locale` 设置的语言翻译 */
+export interface AppError {
+  /** 稳定的错误码，如 `database.connection`、`config.invalid` */
+  code: string;
+  category: "io" | "database" | "config" | "secret" | "format" | "export" | "server" | "job" | "internal";
+  message: string;
+  /** 附加信息，如 `sql_state`、`line`、`errors` */
+  details: Record<string, unknown>;
+}
+
+/** 后台任务的错误，由 `background-error` 事件发送 */
+export interface BackgroundError {
+  /** 出错的后台任务，如 `purge`、`server` */
+  task: string;
+  job_id?: number;
+  error: AppError;
+}
+
+/** 取出错误提示，兼容非 `AppError` 的异常 */
+export function errorMessage(reason: unknown): string {
+  if (typeof reason === "object" && reason !== null && "message" in reason) {
+    return String((reason as AppError).message);
+  }
+  return String(reason);
+}
@@ -2,6 +2,7 @@ import { createApp } from "vue";
 import { createPinia } from "pinia";
 import "virtual:uno.css";
 import "@unocss/reset/eric-meyer.css";
+import { invoke } from "@tauri-apps/api/core";
 import App from "./App.vue";
 
 // 路由
@@ -11,6 +12,9 @@ import router from "./router/";
 const pinia = createPinia();
 
 
+// 后端错误提示的语言
+invoke("set_locale", { locale: navigator.language });
+
 createApp(App).use(pinia).use(router).mount("#app");
 
 // 禁止默认右键菜单
@@ -16,13 +16,8 @@ const useConfigStore = defineStore("config", () => {
   const config = computedAsync<Config>(
     async (_onCancel) => await promise.value,
     {
-      database: {
-        host: "",
-        port: -1,
-        database: "",
-        user: "",
-        password: "",
-      },
+      active: "",
+      profiles: {},
     } as Config,
     evaluating
   );
@@ -41,12 +36,20 @@ const useConfigStore = defineStore("config", () => {
     invoke<Config>("write_config", { config }).then(reload);
   }
 
+  /**
+   * 切换数据库配置，后端会重新连接并清空缓存
+   */
+  function activate(name: string) {
+    return invoke("activate_profile", { name }).then(reload);
+  }
+
   return {
     promise,
     evaluating,
     config,
     reload,
     save,
+    activate,
   };
 });
 
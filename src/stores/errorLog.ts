@@ -1,9 +1,10 @@
 import { defineStore } from "pinia";
 import { useJobStore } from "./job";
 import { computedAsync } from "@vueuse/core";
-import { computed, shallowRef, watchEffect } from "vue";
+import { computed, shallowRef, triggerRef, watchEffect } from "vue";
 import { Channel, invoke } from "@tauri-apps/api/core";
 import { decode } from "@msgpack/msgpack";
+import { tableFromIPC } from "apache-arrow";
 
 export interface ErrorLogSumary {
   load: number;
@@ -22,49 +23,106 @@ export interface ErrorLog {
   [key: string]: number | null;
 }
 
+/** 误差日志的传输格式，与后端 `Transport` 对应 */
+const TRANSPORT: "msgpack" | "arrow" = "arrow";
+
+/** 一条消息中的加载步汇总与误差日志 */
+type Chunk = [ErrorLogSumary[], ErrorLog[]];
+
+/** 解码一条 MessagePack 消息 `[summary, entries]` */
+function decodeMsgPack(response: ArrayBuffer): Chunk {
+  const [summaryArray, errorLogArray] = decode(response) as [
+    Array<[number, number, number]>,
+    Array<[number, number, number, number]>
+  ];
+  return [
+    summaryArray.map(([load, iters, cost]) => ({ load, iters, cost })),
+    errorLogArray.map(([iters, load, error_u, error_phi]) => ({
+      iters,
+      load,
+      error_u,
+      error_phi,
+    })),
+  ];
+}
+
+/** 解码一条 Arrow IPC 消息，每条只包含表 `summary` 或 `entries` 的一个 record batch */
+function decodeArrow(response: ArrayBuffer): Chunk {
+  const table = tableFromIPC(new Uint8Array(response));
+  const column = (name: string) => table.getChild(name)!;
+  if (table.schema.metadata.get("table") === "summary") {
+    const [load, iters, cost] = ["load", "iters", "cost"].map(column);
+    const summary = Array.from({ length: table.numRows }, (_, i) => ({
+      load: load.get(i),
+      iters: iters.get(i),
+      cost: cost.get(i),
+    }));
+    return [summary, []];
+  }
+  // 这几列没有空值，直接取出类型化数组
+  const [iters, load, error_u, error_phi] = ["iters", "load", "error_u", "error_phi"].map(
+    (name) => column(name).toArray()
+  );
+  const errorLog = Array.from({ length: table.numRows }, (_, i) => ({
+    iters: iters[i],
+    load: load[i],
+    error_u: error_u[i],
+    error_phi: error_phi[i],
+  }));
+  return [[], errorLog];
+}
+
 const useLogStore = defineStore("errorLog", () => {
   const jobs = useJobStore();
 
   const summary = shallowRef<ErrorLogSumary[]>([]);
   const errors = shallowRef<ErrorLog[]>([]);
+  let activeChannel: Channel<ArrayBuffer> | undefined;
 
+  /**
+   * 解析一块数据并追加到已有数据之后，空消息表示结束
+   */
   const parseResponse = (response: ArrayBuffer) => {
-    // MessagePack decoding
-    const [summaryArray, errorLogArray] = decode(response) as [
-      Array<[number, number, number]>,
-      Array<[number, number, number, number]>
-    ];
-
-    // error log
-    const errorLog = errorLogArray.map(([iters, load, error_u, error_phi]) => ({
-      iters,
-      load,
-      error_u,
-      error_phi,
-    }));
+    if (response.byteLength === 0) {
+      return;
+    }
+
+    const [summaryRows, errorLog] =
+      TRANSPORT === "arrow" ? decodeArrow(response) : decodeMsgPack(response);
+
     // 加工数据，以用于echarts画图
-    splitErrorLog(errorLog);
-    errors.value = errorLog;
+    splitErrorLog(errorLog, errors.value[errors.value.length - 1]);
+    // 逐项追加，展开参数的个数受调用栈大小限制
+    for (const entry of errorLog) {
+      errors.value.push(entry);
+    }
+    triggerRef(errors);
 
-    // error summary
-    summary.value = summaryArray.map(([load, iters, cost]) => ({
-      load,
-      iters,
-      cost,
-    }));
+    // error summary，只在第一块中
+    if (summaryRows.length > 0) {
+      summary.value = summaryRows;
+    }
   };
 
   watchEffect(() => {
+    summary.value = [];
+    errors.value = [];
     if (jobs.currentJob) {
-      const channel = new Channel<Uint8Array>();
-      channel.onmessage = parseResponse;
+      const channel = new Channel<ArrayBuffer>();
+      // 切换任务后忽略旧任务尚未发送完的数据
+      channel.onmessage = (response) => {
+        if (channel === activeChannel) {
+          parseResponse(response);
+        }
+      };
+      activeChannel = channel;
       invoke("get_error_log", {
         jobId: jobs.currentJob.id,
+        transport: TRANSPORT,
         channel,
       });
     } else {
-      summary.value = [];
-      errors.value = [];
+      activeChannel = undefined;
     }
   });
 
@@ -110,7 +168,10 @@ const useLogStore = defineStore("errorLog", () => {
   };
 });
 
-function splitErrorLog(errors: Array<ErrorLog>) {
+/**
+ * @param previous 上一块数据的最后一项，用于在块的边界插入断点
+ */
+function splitErrorLog(errors: Array<ErrorLog>, previous?: ErrorLog) {
   let idx = 0;
   while (idx < errors.length) {
     let error_u: number | null = null;
@@ -127,10 +188,11 @@ function splitErrorLog(errors: Array<ErrorLog>) {
     errors[idx] = { ...value, error_u, error_phi };
 
     // 插入断点标记在 load 改变的地方
+    const last = idx > 0 ? errors[idx - 1] : previous;
     if (
-      idx > 0 &&
-      errors[idx - 1].iters !== null && // 防止死循环
-      value.load !== errors[idx - 1].load
+      last !== undefined &&
+      last.iters !== null && // 防止死循环
+      value.load !== last.load
     ) {
       errors.splice(idx, 0, {
         iters: null,
@@ -1,94 +1,236 @@
 import { defineStore } from "pinia";
-import { computed, ref, watch } from "vue";
-import { Channel, invoke } from "@tauri-apps/api/core";
-import { decode } from "@msgpack/msgpack";
+import { ref, watch } from "vue";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useConfigStore } from "./config";
 
 export interface JobInfo {
+  /** 数据库分配的编号 */
   id: number;
   name: string;
   queue: string;
   cpus: number;
   parameters?: object;
+  nodes: string[];
+  /** 调度系统，如 `pbs`、`slurm`，无法判断时为空 */
+  scheduler: string;
+  /** 调度系统的原始作业号，如 `12345.pbs01`、`123_[4]` */
+  scheduler_id: string;
+  array_job_id?: string;
+  array_index?: number;
+  /** 日志中作业信息行的全部字段，如 `user`、`walltime` */
+  metadata: Record<string, unknown>;
+  starred: boolean;
+  tags: string[];
+  /** 导入时间，较早导入的任务为空 */
+  imported_at?: string;
+  /** 以下为汇总指标，没有误差日志时为空 */
+  iterations?: number;
+  /** 收敛的加载步数 */
+  load_steps?: number;
+  failed_steps?: number;
+  final_load?: number;
+  /** 第一条与最后一条日志的间隔（秒） */
+  wall_time?: number;
+  /** 移入回收站的时间，未删除时为空 */
+  deleted_at?: string;
+}
+
+/** 任务列表的筛选条件，与后端 `JobFilter` 对应 */
+export interface JobFilter {
+  tag?: string;
+  /** 备注中包含的文字 */
+  note?: string;
+  starred?: boolean;
+  /** 匹配编号、作业号、名字、队列或参数值 */
+  search?: string;
+  /** 为 `true` 时只返回回收站中的任务 */
+  deleted?: boolean;
+  /** 为 `true` 时同时返回汇总指标 */
+  metrics?: boolean;
+}
+
+/** 后端返回的任务，核数的字段名为 `num_cpu` */
+type JobRow = Omit<JobInfo, "cpus"> & { num_cpu: number };
+
+function toJobInfo({ num_cpu, ...row }: JobRow): JobInfo {
+  return { ...row, cpus: num_cpu };
+}
+
+export type JobSortKey =
+  | "id"
+  | "scheduler_id"
+  | "name"
+  | "queue"
+  | "num_cpu"
+  | "imported_at"
+  | "iterations"
+  | "failed_steps"
+  | "final_load"
+  | "wall_time";
+
+/** 分页参数，与后端 `JobPageRequest` 对应 */
+export interface JobPageRequest {
+  sort: JobSortKey;
+  order: "asc" | "desc";
+  cursor?: string;
+  limit: number;
+  /** 跳过的任务数，跳转到没有游标的页时使用 */
+  offset?: number;
+}
+
+interface JobPage {
+  total: number;
+  jobs: JobRow[];
+  next_cursor?: string;
 }
 
 // FIXME: 错误处理 addJob, removeJob
 const useJobStore = defineStore("job", () => {
   const config = useConfigStore();
 
+  /** 当前页的任务 */
   const list = ref<JobInfo[]>([]);
-  const currentJobIndex = ref(-1);
-  const currentJob = computed<JobInfo | undefined>(() => {
-    if (list.value.length > 0) {
-      return list.value[currentJobIndex.value];
-    } else {
-      return undefined;
+  const total = ref(0);
+  const filter = ref<JobFilter>({});
+  const sort = ref<Pick<JobPageRequest, "sort" | "order">>({ sort: "id", order: "asc" });
+  const pageSize = ref(10);
+  const page = ref(1);
+  /** 已知的各页游标，键为页码，第一页没有游标 */
+  let cursors = new Map<number, string>();
+  const currentJob = ref<JobInfo>();
+
+  /** 跳转到第 `target` 页，有游标时按游标翻页，否则按偏移量一次查询 */
+  async function loadPage(target: number) {
+    const cursor = cursors.get(target);
+    const request: JobPageRequest = {
+      ...sort.value,
+      cursor,
+      limit: pageSize.value,
+      offset: target > 1 && cursor === undefined ? (target - 1) * pageSize.value : undefined,
+    };
+    const result = await invoke<JobPage>("get_job_page", {
+      // 列表中显示汇总指标
+      filter: { ...filter.value, metrics: true },
+      page: request,
+    });
+    if (result.next_cursor) {
+      cursors.set(target + 1, result.next_cursor);
     }
-  });
+    page.value = target;
+    total.value = result.total;
+    list.value = result.jobs.map(toJobInfo);
+  }
 
   function addToList(jobId: number) {
-    invoke<JobInfo>("find_job", { jobId }).then((newJob) => {
-      list.value.push(newJob);
-      list.value.sort((a, b) => a.id - b.id);
-      currentJobIndex.value = list.value.indexOf(newJob);
+    invoke<JobRow>("find_job", { jobId }).then((newJob) => {
+      currentJob.value = toJobInfo(newJob);
+      updateList();
     });
   }
 
+  /** 移入回收站 */
   function removeJob(jobId: number) {
     invoke("remove_job", { jobId }).then(() => {
-      list.value = list.value.filter((job) => job.id !== jobId);
       if (currentJob.value?.id === jobId) {
-        currentJobIndex.value = -1;
+        currentJob.value = undefined;
       }
+      updateList();
     });
   }
 
+  function restoreJob(jobId: number) {
+    return invoke("restore_job", { jobId }).then(updateList);
+  }
+
+  /** 在后台彻底删除回收站中的任务，返回将要删除的任务 id，完成后刷新列表 */
+  function purgeJobs(jobIds?: number[]) {
+    return invoke<number[]>("purge_jobs", { request: { job_ids: jobIds } });
+  }
+
   function setCurrent(jobId: number) {
-    currentJobIndex.value = list.value.findIndex((job) => job.id === jobId);
+    const job = list.value.find((job) => job.id === jobId);
+    if (job) {
+      currentJob.value = job;
+    } else {
+      invoke<JobRow>("find_job", { jobId }).then((job) => {
+        currentJob.value = toJobInfo(job);
+      });
+    }
   }
 
-  function updateList() {
-    let currentJobId = currentJob.value?.id;
-    const channel = new Channel();
-    channel.onmessage = (response) => {
-      const jobList = decode(response as ArrayBuffer) as Array<
-        [number, string, string, number, object]
-      >;
-      list.value = jobList.map(([id, name, queue, cpus, parameters]) => ({
-        id,
-        name,
-        queue,
-        cpus,
-        parameters,
-      }));
-
-      if (currentJobIndex.value < 0) {
-        // first time
-        currentJobIndex.value = 0;
-      } else if (currentJobId) {
-        // update job list
-        setCurrent(currentJobId);
+  function setFilter(newFilter: JobFilter) {
+    filter.value = newFilter;
+    updateList();
+  }
+
+  function setSort(newSort: Pick<JobPageRequest, "sort" | "order">) {
+    sort.value = newSort;
+    updateList();
+  }
+
+  function setPageSize(size: number) {
+    pageSize.value = size;
+    updateList();
+  }
+
+  /** 更新本地列表中任务的标签与收藏状态 */
+  function annotate(jobId: number, annotation: Partial<Pick<JobInfo, "tags" | "starred">>) {
+    for (const job of [list.value.find((job) => job.id === jobId), currentJob.value]) {
+      if (job?.id === jobId) {
+        Object.assign(job, annotation);
       }
-    };
-    invoke<JobInfo[]>("get_job_list", { channel }).catch((reason) => {
-      console.error(reason);
-      list.value = [];
-    });
+    }
+  }
+
+  /** 清空游标，重新加载第一页 */
+  function updateList() {
+    cursors = new Map();
+    loadPage(1)
+      .then(() => {
+        if (!currentJob.value) {
+          currentJob.value = list.value[0];
+        }
+      })
+      .catch((reason) => {
+        console.error(reason);
+        list.value = [];
+        total.value = 0;
+      });
   }
 
+  listen<number[]>("jobs-purged", () => {
+    if (filter.value.deleted) {
+      updateList();
+    }
+  });
+
   watch(
     ()  => config.promise,
     (_curr, _prev) => {
+      currentJob.value = undefined;
       updateList();
     },
     { immediate: true }
   );
   return {
     list,
+    total,
+    filter,
+    sort,
+    page,
+    pageSize,
     currentJob,
     addJob: addToList,
     removeJob,
+    restoreJob,
+    purgeJobs,
     updateList,
+    loadPage,
+    setFilter,
+    setSort,
+    setPageSize,
+    annotate,
     setCurrent,
   };
 });